@@ -16,7 +16,7 @@ fn main() {
 
   
     println!("Initializing");
-    sp.init();
+    sp.init().expect("invalid spatial pooler parameters");
 
 
     let mut input = vec![false; sp.num_inputs];
@@ -37,14 +37,11 @@ fn main() {
             let r = classifier.compute(record, val, val as u8, &sp.winner_columns[..], true, true);
             if i == 99 {
                 println!("value: {}", val);
-                for &(ref step, ref probabilities) in &r {
-                    println!("{} {:?}",
+                for step in r.steps() {
+                    println!("{} {:?} {:?}",
                              step,
-                             probabilities
-                                 .iter()
-                                 .enumerate()
-                                 .max_by(|&(_, a), &(_, b)| a.partial_cmp(b).unwrap())
-                                 .unwrap());
+                             r.most_probable_value(step),
+                             r.top_k(step, 3));
                 }
             }
 
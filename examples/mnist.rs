@@ -6,7 +6,7 @@ use std::io::prelude::*;
 use std::path::Path;
 use std::io::BufReader;
 use byteorder::{ReadBytesExt, BigEndian};
-use htm::{SDRClassifier,SpatialPooler};
+use htm::{SDRClassifier,SpatialPooler,ClassificationMetrics};
 
 fn main() {
     let mut images = ImageIter::new("../train-images.idx3-ubyte");
@@ -28,7 +28,7 @@ fn main() {
     sp.syn_perm_options.max = 0.3;
     sp.potential_pct = 15.0 / sp.potential_radius as f64;
     sp.compability_mode = true;
-    sp.init();
+    sp.init().expect("invalid spatial pooler parameters");
 
     let mut classifier: SDRClassifier<u8> = SDRClassifier::new(vec![0], 0.1, 0.3, sp.num_columns);
 
@@ -48,19 +48,18 @@ fn main() {
 
         sp.compute(&input, true);
 
-        let r = classifier.compute(record as u32,
-                                   label as usize,
-                                   label as u8,
-                                   &sp.winner_columns[..],
-                                   true,
-                                   true);
+        classifier.compute(record as u32,
+                           label as usize,
+                           label as u8,
+                           &sp.winner_columns[..],
+                           true,
+                           false);
         record += 1;    
     }
 
     println!("Testing on: {}", test_images.size);
 
-    let mut good = 0;   
-    let total = test_images.size;
+    let mut metrics = ClassificationMetrics::new();
     for _ in 0..test_images.size {
 
         let image = test_images.next().unwrap();
@@ -78,24 +77,21 @@ fn main() {
                                    &sp.winner_columns[..],
                                    false,
                                    true);
-        for &(ref step, ref probabilities) in &r {
-            let (answer, score) = probabilities
-                .iter()
-                .enumerate()
-                .max_by(|&(_, a), &(_, b)| a.partial_cmp(b).unwrap())
-                .unwrap();
-            if label == answer as u8 {
-                good += 1;
-            }
-        }
+        metrics.record(label as usize, r.most_probable_bucket(0));
 
         record += 1;
     }
 
     println!("Accuracy: {}, Total: {} Good: {}",
-             good as f32 / total as f32,
-             total,
-             good);
+             metrics.accuracy().unwrap_or(0.0),
+             metrics.total,
+             metrics.correct);
+    for label in 0..metrics.num_classes() {
+        println!("{}: precision {:?} recall {:?}",
+                 label,
+                 metrics.precision(label),
+                 metrics.recall(label));
+    }
 }
 
 struct ImageIter {
@@ -0,0 +1,43 @@
+extern crate htm;
+
+use htm::{Network, SensorRegion, SPRegion, TMRegion, ClassifierRegion, RegionData,
+          ScalarEncoder, SpatialPooler, TemporalMemory, SDRClassifier};
+
+fn main() {
+    let values: Vec<f64> = (0..1000).map(|i| (i % 10) as f64).collect();
+
+    let sp = SpatialPooler::builder(vec![100], vec![256])
+        .full_potential_radius()
+        .global_inhibition(true)
+        .num_active_columns_per_inh_area(0.02 * 256.0)
+        .build()
+        .expect("invalid spatial pooler parameters");
+
+    let mut net = Network::new();
+    net.add_region("sensor", SensorRegion::new(ScalarEncoder::new(21, 0.0, 10.0, 100, false), values.into_iter())).unwrap();
+    net.add_region("sp", SPRegion::new(sp)).unwrap();
+    net.add_region("tm", TMRegion::new(TemporalMemory::new(256, 8))).unwrap();
+    net.add_region("classifier", ClassifierRegion::new(SDRClassifier::new(vec![0, 1], 0.1, 0.3, 256 * 8))).unwrap();
+
+    net.link("sensor", "encoded", "sp", "bottom_up").unwrap();
+    net.link("sp", "active_columns", "tm", "bottom_up").unwrap();
+    net.link("tm", "active_cells", "classifier", "pattern").unwrap();
+    net.link("sensor", "value", "classifier", "value").unwrap();
+
+    let steps = net.run(990).expect("the network failed");
+    println!("Ran {} steps", steps);
+
+    for _ in 0..10 {
+        net.step().expect("the network failed");
+        if let Some(RegionData::Probabilities(dists)) = net.output("classifier", "probabilities") {
+            for &(step, ref probabilities) in &dists {
+                let best = probabilities
+                    .iter()
+                    .enumerate()
+                    .max_by(|&(_, a), &(_, b)| a.partial_cmp(b).unwrap())
+                    .map(|(bucket, _)| bucket);
+                println!("{} step prediction: bucket {:?}", step, best);
+            }
+        }
+    }
+}
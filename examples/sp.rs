@@ -17,7 +17,7 @@ fn main() {
     {
         print!("Initializing");
         let start = PreciseTime::now();
-        sp.init();
+        sp.init().expect("invalid spatial pooler parameters");
         println!(": {:?}", start.to(PreciseTime::now()));
     }
 
@@ -0,0 +1,113 @@
+use algo::ActualValue;
+
+/**
+ * The result of one `SDRClassifier::compute`: the probability distribution over the
+ * buckets for every prediction step and the actual value the classifier associates
+ * with every bucket at the time of the compute.
+ */
+#[derive(Debug,Clone,PartialEq)]
+pub struct Classification<T>
+    where T: ActualValue
+{
+    /**
+     * Probability of every bucket per step, in the order of the classifier steps.
+     * Empty when the compute did not infer.
+     */
+    pub distributions: Vec<(usize, Vec<f32>)>,
+    /** The actual value of every bucket, `None` for buckets that never learned a value. */
+    pub actual_values: Vec<Option<T>>,
+    /** The label threshold of every bucket, see `SDRClassifier::label_threshold`. */
+    pub thresholds: Vec<f32>,
+}
+
+impl<T> Classification<T>
+    where T: ActualValue
+{
+    pub fn new(distributions: Vec<(usize, Vec<f32>)>, actual_values: Vec<Option<T>>, thresholds: Vec<f32>) -> Classification<T> {
+        Classification {
+            distributions,
+            actual_values,
+            thresholds,
+        }
+    }
+
+    /** The steps with a distribution. */
+    pub fn steps(&self) -> Vec<usize> {
+        self.distributions.iter().map(|&(step, _)| step).collect()
+    }
+
+    /** The likelihood of every bucket `step` records in the future. */
+    pub fn stats(&self, step: usize) -> Option<&[f32]> {
+        self.distributions.iter()
+            .find(|&&(s, _)| s == step)
+            .map(|(_, dist)| &dist[..])
+    }
+
+    /** The likelihood of `bucket` `step` records in the future. */
+    pub fn stat(&self, step: usize, bucket: usize) -> Option<f32> {
+        self.stats(step).and_then(|dist| dist.get(bucket).cloned())
+    }
+
+    /** The bucket with the highest likelihood, `None` when no bucket has any likelihood. */
+    pub fn most_probable_bucket(&self, step: usize) -> Option<usize> {
+        self.top_k(step, 1).first().map(|&(bucket, _)| bucket)
+    }
+
+    /** The actual value of the most probable bucket. */
+    pub fn most_probable_value(&self, step: usize) -> Option<T> {
+        self.most_probable_bucket(step).and_then(|bucket| self.actual_value(bucket).cloned())
+    }
+
+    /**
+     * The `k` most likely buckets with their likelihood, the most likely first.
+     * Buckets without any likelihood are left out.
+     */
+    pub fn top_k(&self, step: usize, k: usize) -> Vec<(usize, f32)> {
+        let mut buckets: Vec<(usize, f32)> = match self.stats(step) {
+            Some(dist) => dist.iter().cloned().enumerate().filter(|&(_, probability)| probability > 0.0).collect(),
+            None => return Vec::new(),
+        };
+        // stable sort, equally likely buckets stay in index order
+        buckets.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+        buckets.truncate(k);
+        buckets
+    }
+
+    /**
+     * Multi-label prediction: the buckets whose likelihood `step` records in the future
+     * is above their threshold, in bucket order.
+     */
+    pub fn labels(&self, step: usize) -> Vec<usize> {
+        match self.stats(step) {
+            Some(dist) => dist.iter()
+                .zip(self.thresholds.iter())
+                .enumerate()
+                .filter(|&(_, (&probability, &threshold))| probability > threshold)
+                .map(|(bucket, _)| bucket)
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /** The actual values of the predicted labels, buckets without a value are left out. */
+    pub fn label_values(&self, step: usize) -> Vec<T> {
+        self.labels(step).into_iter().filter_map(|bucket| self.actual_value(bucket).cloned()).collect()
+    }
+
+    pub fn actual_value(&self, bucket: usize) -> Option<&T> {
+        self.actual_values.get(bucket).and_then(|value| value.as_ref())
+    }
+
+    /**
+     * The actual values weighted by the likelihood of their bucket `step` records in
+     * the future, see `ActualValue::weighted_mean`.
+     */
+    pub fn weighted_value(&self, step: usize) -> Option<T> {
+        let dist = self.stats(step)?;
+        let values: Vec<(T, f32)> = dist.iter()
+            .enumerate()
+            .filter_map(|(bucket, &probability)| self.actual_value(bucket).map(|value| (value.clone(), probability)))
+            .collect();
+        T::weighted_mean(&values)
+    }
+}
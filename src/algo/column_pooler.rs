@@ -0,0 +1,374 @@
+use std;
+use std::cmp;
+use std::fmt;
+use fnv::FnvHashSet;
+use rand::Rng;
+use algo::{Cell, Segment, SpatialPooler};
+use util::UniversalRng;
+
+#[derive(Debug,Clone,PartialEq)]
+pub enum CPError {
+    /** `compute` needs one lateral input per neighbouring column. */
+    LateralInputMismatch { expected: usize, actual: usize },
+}
+
+impl fmt::Display for CPError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CPError::LateralInputMismatch { expected, actual } =>
+                write!(f, "the column pooler has {} lateral inputs, one per neighbouring column, got {}", expected, actual),
+        }
+    }
+}
+
+impl std::error::Error for CPError {
+    fn description(&self) -> &str {
+        match *self {
+            CPError::LateralInputMismatch { .. } => "lateral input mismatch",
+        }
+    }
+}
+
+/**
+ * Output layer of a cortical column for sensorimotor object recognition
+ * (the L2/3 column pooler of htmresearch).
+ *
+ * Every cell has one proximal segment fed by the active cells of a TM-like input
+ * layer, distal segments from the previous activity of the layer itself and distal
+ * segments from the active cells of neighbouring columns (one set per lateral input).
+ * While learning, an object is represented by a fixed random SDR of `sdr_size` cells
+ * that is chosen on the first sensation after a `reset`. During inference the cells
+ * with feedforward support and the most distal support win, so the active set narrows
+ * down to a single object across sensations.
+ *
+ * The cells of the pooler are `Cell { column: self.column, cell }`, so the active cells
+ * of several poolers can be given to each other as lateral input.
+ */
+pub struct ColumnPooler {
+    /** Identifier of this cortical column, used as the `column` of its cells. */
+    pub column: u32,
+    pub cell_count: u32,
+    /** Number of active cells representing an object. */
+    pub sdr_size: usize,
+
+    /** Feedforward overlap needed for a cell to be supported by the input. */
+    pub proximal_min_threshold: u32,
+    pub proximal_connected_permanence: f32,
+    pub proximal_initial_permanence: f32,
+    pub proximal_permanence_increment: f32,
+    pub proximal_permanence_decrement: f32,
+    /** Number of active input cells a proximal segment tries to stay connected to. */
+    pub proximal_sample_size: u32,
+
+    /** Connected active synapses needed for a distal segment to be active. */
+    pub distal_activation_threshold: u32,
+    pub distal_connected_permanence: f32,
+    pub distal_initial_permanence: f32,
+    pub distal_permanence_increment: f32,
+    pub distal_permanence_decrement: f32,
+    pub distal_sample_size: u32,
+
+    /** Keep previously active cells with distal support when too few cells are chosen. */
+    pub inertia: bool,
+
+    pub iteration: u64,
+    /** Sorted active cells. */
+    pub active_cells: Vec<Cell>,
+
+    proximal: Vec<Segment>,
+    internal_distal: Vec<Vec<Segment>>,
+    lateral_distal: Vec<Vec<Vec<Segment>>>,
+
+    pub rand: UniversalRng,
+}
+
+impl ColumnPooler {
+    pub fn new(cell_count: u32, sdr_size: usize, lateral_inputs: usize) -> ColumnPooler {
+        ColumnPooler {
+            column: 0,
+            cell_count,
+            sdr_size,
+
+            proximal_min_threshold: 10,
+            proximal_connected_permanence: 0.5,
+            proximal_initial_permanence: 0.6,
+            proximal_permanence_increment: 0.1,
+            proximal_permanence_decrement: 0.001,
+            proximal_sample_size: 20,
+
+            distal_activation_threshold: 13,
+            distal_connected_permanence: 0.5,
+            distal_initial_permanence: 0.6,
+            distal_permanence_increment: 0.1,
+            distal_permanence_decrement: 0.001,
+            distal_sample_size: 20,
+
+            inertia: true,
+
+            iteration: 0,
+            active_cells: Vec::new(),
+
+            proximal: (0..cell_count).map(|cell| Segment::new(0, cell, 0)).collect(),
+            internal_distal: vec![Vec::new(); cell_count as usize],
+            lateral_distal: vec![vec![Vec::new(); cell_count as usize]; lateral_inputs],
+
+            rand: UniversalRng::from_seed([42, 0, 0, 0]),
+        }
+    }
+
+    /** Sets the column identifier, it must be set before anything is learned. */
+    pub fn set_column(&mut self, column: u32) {
+        self.column = column;
+        for seg in &mut self.proximal {
+            seg.cell.column = column;
+        }
+    }
+
+    pub fn num_lateral_inputs(&self) -> usize {
+        self.lateral_distal.len()
+    }
+
+    /** Clears the activity, the next learning step starts a new object. */
+    pub fn reset(&mut self) {
+        self.active_cells.clear();
+    }
+
+    /**
+     * Runs one sensation.
+     *
+     * `feedforward` are the active cells of the input layer and `growth_candidates`
+     * the input cells new proximal synapses are grown to (usually the winner cells of
+     * the input layer). `lateral` holds the active cells of every neighbouring column,
+     * in the order of the lateral inputs, and must have `num_lateral_inputs` entries.
+     * The lateral cells can be in any order. Panics on another number of lateral inputs,
+     * see `try_compute`.
+     */
+    pub fn compute(&mut self, feedforward: &FnvHashSet<Cell>, growth_candidates: &FnvHashSet<Cell>, lateral: &[&[Cell]], learn: bool) {
+        if let Err(err) = self.try_compute(feedforward, growth_candidates, lateral, learn) {
+            panic!("{}", err);
+        }
+    }
+
+    /** Same as `compute` but a wrong number of lateral inputs is reported as an error and leaves the pooler untouched. */
+    pub fn try_compute(&mut self, feedforward: &FnvHashSet<Cell>, growth_candidates: &FnvHashSet<Cell>, lateral: &[&[Cell]], learn: bool) -> Result<(), CPError> {
+        if lateral.len() != self.lateral_distal.len() {
+            return Err(CPError::LateralInputMismatch { expected: self.lateral_distal.len(), actual: lateral.len() });
+        }
+        let lateral = sorted_inputs(lateral);
+        let lateral: Vec<&[Cell]> = lateral.iter().map(|input| &input[..]).collect();
+        self.iteration += 1;
+        let prev_active = std::mem::take(&mut self.active_cells);
+
+        if learn {
+            self.active_cells = if prev_active.is_empty() {
+                self.random_sdr()
+            } else {
+                prev_active.clone()
+            };
+            self.learn(feedforward, growth_candidates, &prev_active, &lateral);
+        } else {
+            self.active_cells = self.infer(feedforward, &prev_active, &lateral);
+        }
+        Ok(())
+    }
+
+    fn random_sdr(&mut self) -> Vec<Cell> {
+        let amount = cmp::min(self.sdr_size, self.cell_count as usize);
+        let mut arr = vec![0u32; self.cell_count as usize];
+        let range = SpatialPooler::sample_into(&mut self.rand, 0..self.cell_count, amount, &mut arr);
+        let column = self.column;
+        let mut cells: Vec<Cell> = arr[range].iter().map(|&cell| Cell { column, cell }).collect();
+        cells.sort();
+        cells
+    }
+
+    fn learn(&mut self, feedforward: &FnvHashSet<Cell>, growth_candidates: &FnvHashSet<Cell>, prev_active: &[Cell], lateral: &[&[Cell]]) {
+        let mut candidates: Vec<Cell> = growth_candidates.iter().cloned().collect();
+        candidates.sort();
+
+        for cell in &self.active_cells {
+            let seg = &mut self.proximal[cell.cell as usize];
+            seg.adapt(|c| feedforward.contains(c), self.proximal_permanence_increment, self.proximal_permanence_decrement, |_, _| {});
+            let active = seg.synapses.iter().filter(|syn| feedforward.contains(&syn.cell)).count() as u32;
+            if active < self.proximal_sample_size {
+                seg.grow_synapses_from(&candidates, self.proximal_initial_permanence, self.proximal_sample_size - active, &mut self.rand);
+            }
+            seg.last_used = self.iteration;
+        }
+
+        let options = DistalOptions::new(self);
+        if !prev_active.is_empty() {
+            learn_distal(&mut self.internal_distal, &self.active_cells, prev_active, self.iteration, &options, &mut self.rand);
+        }
+        for (segments, input) in self.lateral_distal.iter_mut().zip(lateral.iter()) {
+            if !input.is_empty() {
+                learn_distal(segments, &self.active_cells, input, self.iteration, &options, &mut self.rand);
+            }
+        }
+    }
+
+    fn infer(&self, feedforward: &FnvHashSet<Cell>, prev_active: &[Cell], lateral: &[&[Cell]]) -> Vec<Cell> {
+        let supported: Vec<Cell> = self.proximal.iter()
+            .filter(|seg| {
+                let overlap = seg.synapses.iter()
+                    .filter(|syn| syn.permanence >= self.proximal_connected_permanence && feedforward.contains(&syn.cell))
+                    .count();
+                overlap as u32 >= self.proximal_min_threshold
+            })
+            .map(|seg| seg.cell)
+            .collect();
+
+        let support = self.sorted_distal_support(prev_active, lateral);
+        let mut chosen = Vec::new();
+        choose_by_support(&mut chosen, &supported, &support, self.sdr_size);
+
+        if self.inertia && chosen.len() < self.sdr_size {
+            let inertial: Vec<Cell> = prev_active.iter().filter(|cell| chosen.binary_search(cell).is_err()).cloned().collect();
+            choose_by_support(&mut chosen, &inertial, &support, self.sdr_size);
+        }
+
+        if chosen.len() < self.sdr_size {
+            for cell in supported {
+                if let Err(index) = chosen.binary_search(&cell) {
+                    chosen.insert(index, cell);
+                }
+            }
+        }
+        chosen
+    }
+
+    /**
+     * Number of active distal segments of every cell, internal and lateral ones together.
+     * The cells of `prev_active` and `lateral` can be in any order.
+     */
+    pub fn distal_support(&self, prev_active: &[Cell], lateral: &[&[Cell]]) -> Vec<u32> {
+        let mut prev_active = prev_active.to_vec();
+        prev_active.sort();
+        let lateral = sorted_inputs(lateral);
+        let lateral: Vec<&[Cell]> = lateral.iter().map(|input| &input[..]).collect();
+        self.sorted_distal_support(&prev_active, &lateral)
+    }
+
+    fn sorted_distal_support(&self, prev_active: &[Cell], lateral: &[&[Cell]]) -> Vec<u32> {
+        let mut support = vec![0u32; self.cell_count as usize];
+        let mut count = |containers: &[Vec<Segment>], input: &[Cell]| {
+            if input.is_empty() {
+                return;
+            }
+            for (cell, segments) in containers.iter().enumerate() {
+                for seg in segments {
+                    if num_active_synapses(seg, input, self.distal_connected_permanence) >= self.distal_activation_threshold {
+                        support[cell] += 1;
+                    }
+                }
+            }
+        };
+        count(&self.internal_distal, prev_active);
+        for (containers, input) in self.lateral_distal.iter().zip(lateral.iter()) {
+            count(containers, input);
+        }
+        support
+    }
+
+    pub fn proximal_segment(&self, cell: Cell) -> &Segment {
+        &self.proximal[cell.cell as usize]
+    }
+
+    pub fn internal_segments(&self, cell: Cell) -> &Vec<Segment> {
+        &self.internal_distal[cell.cell as usize]
+    }
+
+    pub fn lateral_segments(&self, input: usize, cell: Cell) -> &Vec<Segment> {
+        &self.lateral_distal[input][cell.cell as usize]
+    }
+}
+
+/** Learning parameters of distal segments, shared with the anchoring of `GridModule`. */
+pub struct DistalOptions {
+    pub column: u32,
+    pub activation_threshold: u32,
+    pub connected: f32,
+    pub initial: f32,
+    pub increment: f32,
+    pub decrement: f32,
+    pub sample_size: u32,
+}
+
+impl DistalOptions {
+    fn new(pooler: &ColumnPooler) -> DistalOptions {
+        DistalOptions {
+            column: pooler.column,
+            activation_threshold: pooler.distal_activation_threshold,
+            connected: pooler.distal_connected_permanence,
+            initial: pooler.distal_initial_permanence,
+            increment: pooler.distal_permanence_increment,
+            decrement: pooler.distal_permanence_decrement,
+            sample_size: pooler.distal_sample_size,
+        }
+    }
+}
+
+/**
+ * Reinforces the active segments of every learning cell towards the sorted `input`,
+ * cells without an active segment grow a new one.
+ */
+pub fn learn_distal<R: Rng>(containers: &mut [Vec<Segment>], learning_cells: &[Cell], input: &[Cell], iteration: u64, options: &DistalOptions, rand: &mut R) {
+    for cell in learning_cells {
+        let segments = &mut containers[cell.cell as usize];
+        let mut learned = false;
+        for seg in segments.iter_mut() {
+            if num_active_synapses(seg, input, options.connected) < options.activation_threshold {
+                continue;
+            }
+            seg.adapt(|c| input.binary_search(c).is_ok(), options.increment, options.decrement, |_, _| {});
+            let potential = seg.synapses.iter().filter(|syn| input.binary_search(&syn.cell).is_ok()).count() as u32;
+            if potential < options.sample_size {
+                seg.grow_synapses_from(input, options.initial, options.sample_size - potential, rand);
+            }
+            seg.last_used = iteration;
+            learned = true;
+        }
+
+        if !learned {
+            let mut seg = Segment::new(options.column, cell.cell, iteration);
+            let candidates: Vec<Cell> = input.iter().filter(|c| *c != cell).cloned().collect();
+            seg.grow_synapses_from(&candidates, options.initial, options.sample_size, rand);
+            segments.push(seg);
+        }
+    }
+}
+
+/**
+ * Adds the cells of `candidates` ordered by decreasing distal support, one support
+ * level at a time, until at least `sdr_size` cells are chosen. Cells without support are skipped.
+ * `chosen` stays sorted.
+ */
+fn choose_by_support(chosen: &mut Vec<Cell>, candidates: &[Cell], support: &[u32], sdr_size: usize) {
+    let mut level = candidates.iter().map(|cell| support[cell.cell as usize]).max().unwrap_or(0);
+    while level > 0 && chosen.len() < sdr_size {
+        for cell in candidates.iter().filter(|cell| support[cell.cell as usize] == level) {
+            if let Err(index) = chosen.binary_search(cell) {
+                chosen.insert(index, *cell);
+            }
+        }
+        level -= 1;
+    }
+}
+
+/** Number of connected synapses of the segment whose cell is in the sorted `input`. */
+pub fn num_active_synapses(segment: &Segment, input: &[Cell], connected: f32) -> u32 {
+    segment.synapses.iter()
+        .filter(|syn| syn.permanence >= connected && input.binary_search(&syn.cell).is_ok())
+        .count() as u32
+}
+
+/** Sorted copies of the lateral inputs, the segments look their cells up with `binary_search`. */
+fn sorted_inputs(lateral: &[&[Cell]]) -> Vec<Vec<Cell>> {
+    lateral.iter()
+        .map(|input| {
+            let mut cells = input.to_vec();
+            cells.sort();
+            cells
+        })
+        .collect()
+}
@@ -0,0 +1,314 @@
+use std;
+use std::fmt;
+use std::f32::consts::PI;
+use fnv::FnvHashSet;
+use rand::Rng;
+use algo::{Cell, Segment};
+use algo::column_pooler::{DistalOptions, learn_distal, num_active_synapses};
+use util::UniversalRng;
+
+#[derive(Debug,Clone,PartialEq)]
+pub enum GridError {
+    NoCells,
+    InvalidScale(f32),
+}
+
+impl fmt::Display for GridError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            GridError::NoCells => write!(f, "a grid module needs at least one cell per axis"),
+            GridError::InvalidScale(scale) => write!(f, "the scale of a grid module must be positive and finite, got {}", scale),
+        }
+    }
+}
+
+impl std::error::Error for GridError {
+    fn description(&self) -> &str {
+        match *self {
+            GridError::NoCells => "grid module without cells",
+            GridError::InvalidScale(_) => "invalid grid module scale",
+        }
+    }
+}
+
+/**
+ * A 2D toroidal grid cell module. The module tracks one or more bumps of activity,
+ * each one a phase in `[0, 1)²`, and the cells are a `cells_per_axis`² sheet tiling
+ * that phase space. Moving by `scale` along the module orientation wraps the bumps
+ * around the whole sheet once.
+ *
+ * Locations are anchored to sensory input through distal segments: while learning,
+ * the active grid cells grow segments to the sensed feature, later the same feature
+ * activates those cells again and the bumps jump to their phases.
+ */
+pub struct GridModule {
+    /** Identifier of the module, used as the `column` of its cells. */
+    pub id: u32,
+    pub cells_per_axis: u32,
+    /** Distance travelled for one full period of the module. */
+    pub scale: f32,
+    /** Orientation of the module in radians. */
+    pub orientation: f32,
+    /** Active bumps, an empty module has no location. */
+    pub phases: Vec<(f32, f32)>,
+
+    pub activation_threshold: u32,
+    pub connected_permanence: f32,
+    pub initial_permanence: f32,
+    pub permanence_increment: f32,
+    pub permanence_decrement: f32,
+    pub sample_size: u32,
+
+    pub iteration: u64,
+    segments: Vec<Vec<Segment>>,
+}
+
+impl GridModule {
+    /** Panics when `try_new` fails. */
+    pub fn new(cells_per_axis: u32, scale: f32, orientation: f32) -> GridModule {
+        match GridModule::try_new(cells_per_axis, scale, orientation) {
+            Ok(module) => module,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /** Fails without cells or with a scale that is not positive and finite. */
+    pub fn try_new(cells_per_axis: u32, scale: f32, orientation: f32) -> Result<GridModule, GridError> {
+        if cells_per_axis == 0 {
+            return Err(GridError::NoCells);
+        }
+        if !(scale.is_finite() && scale > 0.0) {
+            return Err(GridError::InvalidScale(scale));
+        }
+        let num_cells = (cells_per_axis * cells_per_axis) as usize;
+        Ok(GridModule {
+            id: 0,
+            cells_per_axis,
+            scale,
+            orientation,
+            phases: Vec::new(),
+
+            activation_threshold: 8,
+            connected_permanence: 0.5,
+            initial_permanence: 0.6,
+            permanence_increment: 0.1,
+            permanence_decrement: 0.001,
+            sample_size: 10,
+
+            iteration: 0,
+            segments: vec![Vec::new(); num_cells],
+        })
+    }
+
+    pub fn num_cells(&self) -> usize {
+        (self.cells_per_axis * self.cells_per_axis) as usize
+    }
+
+    pub fn reset(&mut self) {
+        self.phases.clear();
+    }
+
+    /** Replaces the bumps with a single one at a random phase. */
+    pub fn activate_random_location<R: Rng>(&mut self, rand: &mut R) {
+        self.phases.clear();
+        self.phases.push((rand.next_f32(), rand.next_f32()));
+    }
+
+    /** Path integration, moves every bump by the displacement `(dx, dy)`. */
+    pub fn move_by(&mut self, dx: f32, dy: f32) {
+        let (sin, cos) = self.orientation.sin_cos();
+        let px = (dx * cos - dy * sin) / self.scale;
+        let py = (dx * sin + dy * cos) / self.scale;
+        for phase in &mut self.phases {
+            *phase = (wrap(phase.0 + px), wrap(phase.1 + py));
+        }
+    }
+
+    /** The cell whose area of the sheet contains `phase`. */
+    pub fn cell_for_phase(&self, phase: (f32, f32)) -> u32 {
+        let n = self.cells_per_axis;
+        let x = ((phase.0 * n as f32) as u32).min(n - 1);
+        let y = ((phase.1 * n as f32) as u32).min(n - 1);
+        x * n + y
+    }
+
+    /** Phase of the center of `cell`. */
+    pub fn cell_phase(&self, cell: u32) -> (f32, f32) {
+        let n = self.cells_per_axis as f32;
+        let x = (cell / self.cells_per_axis) as f32;
+        let y = (cell % self.cells_per_axis) as f32;
+        ((x + 0.5) / n, (y + 0.5) / n)
+    }
+
+    /** Sorted indices of the active cells. */
+    pub fn active_cells(&self) -> Vec<u32> {
+        let mut cells: Vec<u32> = self.phases.iter().map(|&phase| self.cell_for_phase(phase)).collect();
+        cells.sort();
+        cells.dedup();
+        cells
+    }
+
+    /**
+     * Associates the current location with the sorted `sensory` cells. Returns false when
+     * there is nothing to learn, i.e. no active location or no sensory input.
+     */
+    pub fn learn<R: Rng>(&mut self, sensory: &[Cell], rand: &mut R) -> bool {
+        self.iteration += 1;
+        let id = self.id;
+        let active: Vec<Cell> = self.active_cells().into_iter().map(|cell| Cell { column: id, cell }).collect();
+        let options = DistalOptions {
+            column: id,
+            activation_threshold: self.activation_threshold,
+            connected: self.connected_permanence,
+            initial: self.initial_permanence,
+            increment: self.permanence_increment,
+            decrement: self.permanence_decrement,
+            sample_size: self.sample_size,
+        };
+        learn_distal(&mut self.segments, &active, sensory, self.iteration, &options, rand);
+        !active.is_empty() && !sensory.is_empty()
+    }
+
+    /**
+     * Moves the bumps to the cells with an active segment for the sorted `sensory` cells.
+     * Returns false and keeps the current location when the input is not recognized.
+     */
+    pub fn anchor(&mut self, sensory: &[Cell]) -> bool {
+        let anchored: Vec<u32> = self.segments.iter()
+            .enumerate()
+            .filter(|&(_, segments)| segments.iter().any(|seg| num_active_synapses(seg, sensory, self.connected_permanence) >= self.activation_threshold))
+            .map(|(cell, _)| cell as u32)
+            .collect();
+        if anchored.is_empty() {
+            return false;
+        }
+        self.phases = anchored.into_iter().map(|cell| self.cell_phase(cell)).collect();
+        true
+    }
+
+    pub fn segments(&self, cell: u32) -> &Vec<Segment> {
+        &self.segments[cell as usize]
+    }
+}
+
+fn wrap(phase: f32) -> f32 {
+    let wrapped = phase - phase.floor();
+    if wrapped >= 1.0 { 0.0 } else { wrapped }
+}
+
+/**
+ * A set of grid cell modules that together encode a location.
+ * The active cells of module `i` are reported as `Cell { column: column_offset + i, cell }`,
+ * `column_offset` must be at least the column count of a `TemporalMemory` to feed them
+ * through `TemporalMemory::compute_external`.
+ */
+pub struct GridLocationLayer {
+    pub modules: Vec<GridModule>,
+    pub column_offset: u32,
+    pub rand: UniversalRng,
+}
+
+impl GridLocationLayer {
+    /** The cells of the modules get the columns from `column_offset` on. */
+    pub fn new(mut modules: Vec<GridModule>, column_offset: u32) -> GridLocationLayer {
+        for (id, module) in modules.iter_mut().enumerate() {
+            module.id = id as u32;
+        }
+        GridLocationLayer {
+            modules,
+            column_offset,
+            rand: UniversalRng::from_seed([42, 0, 0, 0]),
+        }
+    }
+
+    /**
+     * `count` modules of `cells_per_axis`² cells, module `i` has a scale of
+     * `min_scale * scale_ratio^i` and the orientations are spread evenly over 60 degrees.
+     * Panics when `try_with_modules` fails.
+     */
+    pub fn with_modules(count: usize, cells_per_axis: u32, min_scale: f32, scale_ratio: f32, column_offset: u32) -> GridLocationLayer {
+        match GridLocationLayer::try_with_modules(count, cells_per_axis, min_scale, scale_ratio, column_offset) {
+            Ok(layer) => layer,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /** Fails when one of the modules cannot be created, see `GridModule::try_new`. */
+    pub fn try_with_modules(count: usize, cells_per_axis: u32, min_scale: f32, scale_ratio: f32, column_offset: u32) -> Result<GridLocationLayer, GridError> {
+        let modules = (0..count)
+            .map(|i| {
+                let orientation = (PI / 3.0) * i as f32 / count as f32;
+                GridModule::try_new(cells_per_axis, min_scale * scale_ratio.powi(i as i32), orientation)
+            })
+            .collect::<Result<Vec<GridModule>, GridError>>()?;
+        Ok(GridLocationLayer::new(modules, column_offset))
+    }
+
+    pub fn num_cells(&self) -> usize {
+        self.modules.iter().map(|module| module.num_cells()).sum()
+    }
+
+    pub fn reset(&mut self) {
+        for module in &mut self.modules {
+            module.reset();
+        }
+    }
+
+    /** Starts a new environment at a random location in every module. */
+    pub fn activate_random_location(&mut self) {
+        for module in &mut self.modules {
+            module.activate_random_location(&mut self.rand);
+        }
+    }
+
+    pub fn move_by(&mut self, dx: f32, dy: f32) {
+        for module in &mut self.modules {
+            module.move_by(dx, dy);
+        }
+    }
+
+    /**
+     * Learns the association of the current location with `sensory`, or when `learn` is false
+     * anchors the modules that recognize `sensory`. Returns whether any module learned the
+     * location, respectively was anchored.
+     */
+    pub fn sensory_compute(&mut self, sensory: &FnvHashSet<Cell>, learn: bool) -> bool {
+        let mut input: Vec<Cell> = sensory.iter().cloned().collect();
+        input.sort();
+
+        if learn {
+            let mut learned = false;
+            for module in &mut self.modules {
+                learned |= module.learn(&input, &mut self.rand);
+            }
+            learned
+        } else {
+            let mut anchored = false;
+            for module in &mut self.modules {
+                anchored |= module.anchor(&input);
+            }
+            anchored
+        }
+    }
+
+    /** The active grid cells, one column per module starting at `column_offset`. */
+    pub fn active_cells(&self) -> FnvHashSet<Cell> {
+        let mut cells = FnvHashSet::default();
+        for module in &self.modules {
+            let column = self.column_offset + module.id;
+            cells.extend(module.active_cells().into_iter().map(|cell| Cell { column, cell }));
+        }
+        cells
+    }
+
+    /** The active grid cells as sorted indices, the modules are laid out one after another. */
+    pub fn sdr(&self) -> Vec<usize> {
+        let mut sdr = Vec::new();
+        let mut offset = 0;
+        for module in &self.modules {
+            sdr.extend(module.active_cells().into_iter().map(|cell| offset + cell as usize));
+            offset += module.num_cells();
+        }
+        sdr
+    }
+}
@@ -0,0 +1,184 @@
+use std::cmp::Ordering;
+
+/** How a `KNNClassifier` measures the distance between two SDRs. */
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum KNNDistance {
+    /** The active bits of the input missing from the prototype, NuPIC's raw overlap. */
+    Overlap,
+    /** The bits active in only one of the SDRs. */
+    Hamming,
+    /** One minus the cosine similarity of the SDRs, in `[0, 1]`. */
+    Cosine,
+}
+
+impl KNNDistance {
+    /** The distance between the sorted, deduplicated SDRs `a` and `b`. */
+    pub fn distance(self, a: &[usize], b: &[usize]) -> f32 {
+        let overlap = overlap(a, b) as f32;
+        match self {
+            KNNDistance::Overlap => a.len() as f32 - overlap,
+            KNNDistance::Hamming => (a.len() + b.len()) as f32 - 2.0 * overlap,
+            KNNDistance::Cosine => {
+                if a.is_empty() || b.is_empty() {
+                    1.0
+                } else {
+                    1.0 - overlap / ((a.len() * b.len()) as f32).sqrt()
+                }
+            }
+        }
+    }
+}
+
+/** The number of bits active in both sorted SDRs. */
+fn overlap(a: &[usize], b: &[usize]) -> usize {
+    let (mut i, mut j, mut count) = (0, 0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            Ordering::Less => i += 1,
+            Ordering::Greater => j += 1,
+            Ordering::Equal => {
+                count += 1;
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    count
+}
+
+/** A stored sample of a `KNNClassifier`. */
+#[derive(Debug,Clone,PartialEq)]
+pub struct Prototype<T> {
+    /** The active bits, sorted without duplicates. */
+    pub pattern: Vec<usize>,
+    pub label: T,
+    /** Samples learned together can be removed together with `remove_partition`. */
+    pub partition: Option<u32>,
+}
+
+/**
+ * K-nearest-neighbour classifier over SDRs. Every learned SDR is stored as a prototype
+ * with its label, an input gets the labels of its `k` nearest prototypes, so a class
+ * can be learned from a few samples. Patterns are lists of active bits in any order,
+ * the `winner_columns` of a `SpatialPooler` can be used directly.
+ *
+ * Parameters are public and can be changed at any time.
+ */
+pub struct KNNClassifier<T>
+    where T: Clone + PartialEq
+{
+    /** The number of neighbours voting for the label. */
+    pub k: usize,
+    pub distance: KNNDistance,
+    /** The vote of a neighbour is `1 / (distance + 1)` instead of 1. */
+    pub distance_weighting: bool,
+    /** When full, learning replaces the oldest prototype. `None` keeps every sample. */
+    pub max_prototypes: Option<usize>,
+
+    prototypes: Vec<Prototype<T>>,
+}
+
+impl<T> KNNClassifier<T>
+    where T: Clone + PartialEq
+{
+    pub fn new(k: usize, distance: KNNDistance) -> KNNClassifier<T> {
+        KNNClassifier {
+            k,
+            distance,
+            distance_weighting: false,
+            max_prototypes: None,
+            prototypes: Vec::new(),
+        }
+    }
+
+    pub fn prototypes(&self) -> &[Prototype<T>] {
+        &self.prototypes
+    }
+
+    pub fn len(&self) -> usize {
+        self.prototypes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.prototypes.is_empty()
+    }
+
+    pub fn learn(&mut self, pattern: &[usize], label: T) {
+        self.learn_partition(pattern, label, None);
+    }
+
+    /** Learns a sample belonging to `partition`, see `remove_partition`. */
+    pub fn learn_partition(&mut self, pattern: &[usize], label: T, partition: Option<u32>) {
+        let prototype = Prototype {
+            pattern: normalize(pattern),
+            label,
+            partition,
+        };
+        match self.max_prototypes {
+            Some(0) => {}
+            Some(max) if self.prototypes.len() >= max => {
+                let excess = self.prototypes.len() + 1 - max;
+                self.prototypes.drain(..excess);
+                self.prototypes.push(prototype);
+            }
+            _ => self.prototypes.push(prototype),
+        }
+    }
+
+    /** Removes the prototypes of `partition`, returns how many were removed. */
+    pub fn remove_partition(&mut self, partition: u32) -> usize {
+        let len = self.prototypes.len();
+        self.prototypes.retain(|prototype| prototype.partition != Some(partition));
+        len - self.prototypes.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.prototypes.clear();
+    }
+
+    /** The `k` nearest prototypes as `(index, distance)`, the nearest first. */
+    pub fn neighbours(&self, pattern: &[usize]) -> Vec<(usize, f32)> {
+        let pattern = normalize(pattern);
+        let mut distances: Vec<(usize, f32)> = self.prototypes.iter()
+            .map(|prototype| self.distance.distance(&pattern, &prototype.pattern))
+            .enumerate()
+            .collect();
+        // stable sort, the older prototype wins a tie
+        distances.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+        distances.truncate(self.k);
+        distances
+    }
+
+    /** The labels of the nearest prototypes with their share of the votes, the largest first. */
+    pub fn votes(&self, pattern: &[usize]) -> Vec<(T, f32)> {
+        let mut votes: Vec<(T, f32)> = Vec::new();
+        for (index, distance) in self.neighbours(pattern) {
+            let weight = if self.distance_weighting { 1.0 / (distance + 1.0) } else { 1.0 };
+            let label = &self.prototypes[index].label;
+            match votes.iter_mut().find(|vote| vote.0 == *label) {
+                Some(vote) => vote.1 += weight,
+                None => votes.push((label.clone(), weight)),
+            }
+        }
+
+        let total: f32 = votes.iter().map(|&(_, weight)| weight).sum();
+        for vote in &mut votes {
+            vote.1 /= total;
+        }
+        // stable sort, a tie goes to the label of the nearest prototype
+        votes.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+        votes
+    }
+
+    /** The label with the most votes, `None` without prototypes. */
+    pub fn infer(&self, pattern: &[usize]) -> Option<T> {
+        self.votes(pattern).into_iter().next().map(|(label, _)| label)
+    }
+}
+
+fn normalize(pattern: &[usize]) -> Vec<usize> {
+    let mut pattern = pattern.to_vec();
+    pattern.sort();
+    pattern.dedup();
+    pattern
+}
@@ -1,11 +1,25 @@
 mod spatial_pooler;
+mod spatial_pooler_builder;
 mod potential_pool;
 mod sdr_classifier;
+mod classification;
+mod knn_classifier;
 mod topology;
 mod temporal_memory;
+mod observer;
+mod union_pooler;
+mod column_pooler;
+mod grid_module;
 
-pub use self::spatial_pooler::{SpatialPooler, SynapsePermenenceOptions};
-pub use self::temporal_memory::{TemporalMemory, Cell, Segment, Synapse};
-pub use self::sdr_classifier::SDRClassifier;
+pub use self::spatial_pooler::{SpatialPooler, SynapsePermenenceOptions, SPError};
+pub use self::spatial_pooler_builder::SpatialPoolerBuilder;
+pub use self::temporal_memory::{TemporalMemory, TMError, TMState, EvictionPolicy, ForgettingOptions, PruneReport, Cell, Segment, SegmentRef, Synapse, SynapseLink, PredictionBranch, ColumnPrediction, ColumnStatistics};
+pub use self::sdr_classifier::{SDRClassifier, ClassifierError, ActualValue, ClassifierMode};
+pub use self::classification::Classification;
+pub use self::knn_classifier::{KNNClassifier, KNNDistance, Prototype};
 pub use self::topology::Topology;
-pub use self::potential_pool::PotentialPool;
\ No newline at end of file
+pub use self::potential_pool::PotentialPool;
+pub use self::observer::LearningObserver;
+pub use self::union_pooler::{UnionPooler, UPError};
+pub use self::column_pooler::{ColumnPooler, CPError};
+pub use self::grid_module::{GridModule, GridLocationLayer, GridError};
\ No newline at end of file
@@ -0,0 +1,34 @@
+use algo::{Cell, SegmentRef};
+
+/**
+ * Receives the learning events of `SpatialPooler` and `TemporalMemory`.
+ * Every method does nothing by default, implement the ones you are interested in
+ * and install the observer through the `observer` field of the algorithm.
+ * Nothing is computed for the events when no observer is installed.
+ */
+pub trait LearningObserver {
+    /** A distal segment was added to a cell, either new or in a free slot. */
+    fn segment_created(&mut self, _segment: &SegmentRef) {}
+    /** A full cell replaced one of its segments with a new one. */
+    fn segment_recycled(&mut self, _segment: &SegmentRef) {}
+    /** A segment was removed by forgetting, its slot is free again. */
+    fn segment_destroyed(&mut self, _segment: &SegmentRef) {}
+
+    fn synapse_grown(&mut self, _segment: &SegmentRef, _presynaptic: Cell, _permanence: f32) {}
+    fn synapse_destroyed(&mut self, _segment: &SegmentRef, _presynaptic: Cell) {}
+    /** A distal synapse crossed `connected_permanence`, upwards when `connected` is true. */
+    fn synapse_connection_changed(&mut self, _segment: &SegmentRef, _presynaptic: Cell, _connected: bool) {}
+
+    /** A proximal synapse of the spatial pooler crossed the connected permanence. */
+    fn proximal_connection_changed(&mut self, _column: usize, _input: usize, _connected: bool) {}
+    fn boost_changed(&mut self, _column: usize, _old: f32, _new: f32) {}
+    /** The column was raised by `bump_up_weak_columns`. */
+    fn column_bumped(&mut self, _column: usize) {}
+}
+
+#[inline]
+pub fn notify<F: FnOnce(&mut dyn LearningObserver)>(observer: &mut Option<Box<dyn LearningObserver + Send>>, event: F) {
+    if let Some(ref mut observer) = *observer {
+        event(&mut **observer);
+    }
+}
@@ -34,6 +34,10 @@ impl PotentialPool {
         }
     }
 
+    pub fn num_columns(&self) -> usize {
+        self.connected_len.len()
+    }
+
     pub fn setup_pool<R: Rng>(&mut self,
                               index: usize,
                               potential: &[usize],
@@ -118,6 +122,13 @@ impl PotentialPool {
         self.synapses.children_sized(index, self.connected_len[index])
     }
 
+    /** Sorted input indices of the connected synapses of a column. */
+    pub fn connected_inputs(&self, index: usize) -> Vec<usize> {
+        let mut inputs: Vec<usize> = self.connected_by_column(index).iter().map(|syn| syn.index).collect();
+        inputs.sort();
+        inputs
+    }
+
     pub fn connections_by_column(&self, index: usize) -> &[Synapse] {
         self.synapses.children(index)
     }
@@ -1,9 +1,162 @@
 use std::collections::VecDeque;
 use std;
+use std::fmt;
+use std::cmp;
+use fnv::FnvHashMap;
 use util::numext::*;
+use algo::Classification;
+
+#[derive(Debug,Clone,PartialEq)]
+pub enum ClassifierError {
+    NoSteps,
+    DuplicateStep(usize),
+    /** The learning rate `alpha` must be positive. */
+    InvalidAlpha(f32),
+    /** `act_value_alpha` must be in `[0, 1]`. */
+    ActValueAlphaOutOfRange(f32),
+    /** The classifier needs at least one input bit. */
+    EmptyInput,
+    /** Record numbers must not decrease, the pattern history would be meaningless. */
+    RecordOutOfOrder { record_num: u32, previous: u32 },
+    /** A record lists the same bucket twice. */
+    DuplicateBucket(usize),
+    /** Label thresholds are probabilities, they must be in `[0, 1]`. */
+    ThresholdOutOfRange(f32),
+}
+
+impl fmt::Display for ClassifierError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ClassifierError::NoSteps => write!(f, "the classifier needs at least one prediction step"),
+            ClassifierError::DuplicateStep(step) => write!(f, "prediction step {} is listed more than once", step),
+            ClassifierError::InvalidAlpha(alpha) => write!(f, "alpha {} must be positive", alpha),
+            ClassifierError::ActValueAlphaOutOfRange(alpha) => write!(f, "act_value_alpha {} is not in [0, 1]", alpha),
+            ClassifierError::EmptyInput => write!(f, "the classifier needs at least one input bit"),
+            ClassifierError::RecordOutOfOrder { record_num, previous } => write!(f, "record {} comes after record {}", record_num, previous),
+            ClassifierError::DuplicateBucket(bucket) => write!(f, "bucket {} is listed more than once", bucket),
+            ClassifierError::ThresholdOutOfRange(threshold) => write!(f, "label threshold {} is not in [0, 1]", threshold),
+        }
+    }
+}
+
+impl std::error::Error for ClassifierError {
+    fn description(&self) -> &str {
+        match *self {
+            ClassifierError::NoSteps => "no prediction steps",
+            ClassifierError::DuplicateStep(_) => "duplicate prediction step",
+            ClassifierError::InvalidAlpha(_) => "invalid alpha",
+            ClassifierError::ActValueAlphaOutOfRange(_) => "act_value_alpha out of range",
+            ClassifierError::EmptyInput => "empty classifier input",
+            ClassifierError::RecordOutOfOrder { .. } => "record out of order",
+            ClassifierError::DuplicateBucket(_) => "duplicate bucket",
+            ClassifierError::ThresholdOutOfRange(_) => "label threshold out of range",
+        }
+    }
+}
+
+/**
+ * How an `SDRClassifier` tracks the actual value of a bucket. Numbers keep a running
+ * average, categories keep the last value, as every bucket only ever sees one category.
+ * The defaults are the category behavior, `impl ActualValue for MyCategory {}` is enough.
+ * Values with both `as_f64` and `from_f64` are averaged as `f64` by the classifier and
+ * only converted back when a value is returned, the others are averaged with `blend`.
+ */
+pub trait ActualValue: Clone {
+    /** The bucket value after learning `value`, `alpha` is the weight of the new value. */
+    fn blend(&self, value: &Self, _alpha: f32) -> Self {
+        value.clone()
+    }
+
+    /** The mean of `values` weighted by their probability, `None` when it has no meaning. */
+    fn weighted_mean(_values: &[(Self, f32)]) -> Option<Self> {
+        None
+    }
+
+    /** The value as a number for the error metrics, `None` for categories. */
+    fn as_f64(&self) -> Option<f64> {
+        None
+    }
+
+    /** The value nearest to `number`, `None` for categories. */
+    fn from_f64(_number: f64) -> Option<Self> {
+        None
+    }
+}
+
+macro_rules! impl_float_actual_value {
+    ($($name:ident),*) => {$(
+        impl ActualValue for $name {
+            fn blend(&self, value: &$name, alpha: f32) -> $name {
+                *self + alpha as $name * (*value - *self)
+            }
+
+            fn weighted_mean(values: &[($name, f32)]) -> Option<$name> {
+                let total: f32 = values.iter().map(|&(_, probability)| probability).sum();
+                if total <= 0.0 {
+                    return None;
+                }
+                Some(values.iter().map(|&(value, probability)| value * probability as $name).sum::<$name>() / total as $name)
+            }
+
+            fn as_f64(&self) -> Option<f64> {
+                Some(*self as f64)
+            }
+
+            fn from_f64(number: f64) -> Option<$name> {
+                Some(number as $name)
+            }
+        }
+    )*}
+}
+
+/** Integers are averaged as `f64` and rounded to the nearest integer. */
+macro_rules! impl_integer_actual_value {
+    ($($name:ident),*) => {$(
+        impl ActualValue for $name {
+            fn blend(&self, value: &$name, alpha: f32) -> $name {
+                (*self as f64).blend(&(*value as f64), alpha).round() as $name
+            }
+
+            fn weighted_mean(values: &[($name, f32)]) -> Option<$name> {
+                let values: Vec<(f64, f32)> = values.iter().map(|&(value, probability)| (value as f64, probability)).collect();
+                f64::weighted_mean(&values).map(|mean| mean.round() as $name)
+            }
+
+            fn as_f64(&self) -> Option<f64> {
+                Some(*self as f64)
+            }
+
+            fn from_f64(number: f64) -> Option<$name> {
+                Some(number.round() as $name)
+            }
+        }
+    )*}
+}
+
+impl_float_actual_value!(f32, f64);
+impl_integer_actual_value!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+impl ActualValue for bool {}
+impl ActualValue for char {}
+impl ActualValue for String {}
+
+/** How the bucket activations of an `SDRClassifier` become probabilities. */
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum ClassifierMode {
+    /**
+     * The activations are cut at 0, squared and normalized. The weights learn the
+     * difference between the target and these probabilities.
+     */
+    Squared,
+    /**
+     * The activations go through a softmax and the weights follow the cross-entropy
+     * gradient, like htm.core. The probabilities are better calibrated.
+     */
+    Softmax,
+}
 
 pub struct SDRClassifier<T>
-    where T: std::clone::Clone
+    where T: ActualValue
 {
     /**
      * The alpha used to adapt the weight matrix during
@@ -26,9 +179,9 @@ pub struct SDRClassifier<T>
      */
     record_num_minus_learn_iteration: i32, //= -1;
     /**
-	 * This contains the highest value we've ever seen from the list of active cell indexes
-	 * from the TM (patternNZ). It is used to pre-allocate fixed size arrays that holds the weights.
-	 */
+     * This contains the highest value we've ever seen from the list of active cell indexes
+     * from the TM (patternNZ). It grows with the patterns, the weights are stored per active bit.
+     */
     max_input_idx: usize, // = 0;
 
     /**
@@ -39,31 +192,48 @@ pub struct SDRClassifier<T>
     max_bucket_idx: usize,
 
     /**
-	 * The connection weight matrix
-	 */
-    weight_matrix: Vec<Vec<Vec<f32>>>,
-    /** The sequence different steps of multi-step predictions */
-    steps: Vec<u8>,
+     * The connection weight matrix, sparse over the input: only the bits that have been
+     * active during learning have a row, with one weight per bucket. Rows are padded
+     * with zeros when they learn, missing weights are zero.
+     */
+    weight_matrix: Vec<FnvHashMap<usize, Vec<f32>>>,
+    /** The sequence different steps of multi-step predictions, `weight_matrix` has one matrix per step in the same order */
+    steps: Vec<usize>,
     /**
      * History of the last _maxSteps activation patterns. We need to keep
      * these so that we can associate the current iteration's classification
      * with the activationPattern from N steps ago
      */
     pattern_history: VecDeque<(u32, Vec<usize>)>,
+    /** The history holds the current pattern and one per step up to the largest one. */
+    history_size: usize,
     /**
      * This keeps track of the actual value to use for each bucket index. We
      * start with 1 bucket, no actual value so that the first infer has something
      * to return
      */
     actual_values: Vec<Option<T>>,
+    /**
+     * The unrounded running average of every numeric bucket, see `ActualValue::from_f64`.
+     * Rounding on every update would keep an integer bucket from moving by small steps.
+     */
+    averages: Vec<Option<f64>>,
 
     error: Vec<f32>,
-    infer: Vec<(u8, Vec<f32>)>,
-    infer_empty: Vec<(u8, Vec<f32>)>,
+    infer: Vec<(usize, Vec<f32>)>,
+    /** How activations become probabilities, for inference and learning. */
+    mode: ClassifierMode,
+    /**
+     * The probability above which a bucket is predicted as a label, `None` is the
+     * probability of a uniform distribution over the buckets.
+     */
+    label_threshold: Option<f32>,
+    /** Thresholds of single buckets, they override `label_threshold`. */
+    bucket_thresholds: Vec<Option<f32>>,
 }
 
 impl<T> SDRClassifier<T>
-    where T: std::clone::Clone
+    where T: ActualValue
 {
     /**
      * Constructor for the SDRClassifier
@@ -74,33 +244,148 @@ impl<T> SDRClassifier<T>
      * @param actValueAlpha Used to track the actual value withing each bucket. A lower 
      * 		  actValueAlpha results in longer term memory.
      * @param verbosity Verbosity level, can be 0, 1, or 2.
+     *
+     * Panics without steps or with a `column_size` of 0. `alpha` and `act_value_alpha`
+     * are taken as they are, `try_new` validates them as well.
      */
-    pub fn new(steps: Vec<u8>,
+    pub fn new(steps: Vec<usize>,
                alpha: f32,
                act_value_alpha: f32,
                column_size: usize)
                -> SDRClassifier<T> {
-        let max = *steps.iter().max().unwrap() as usize + 1;
+        SDRClassifier::new_with_mode(steps, alpha, act_value_alpha, column_size, ClassifierMode::Squared)
+    }
+
+    /** Same as `new` with the given `mode` instead of `ClassifierMode::Squared`. */
+    pub fn new_with_mode(steps: Vec<usize>,
+                         alpha: f32,
+                         act_value_alpha: f32,
+                         column_size: usize,
+                         mode: ClassifierMode)
+                         -> SDRClassifier<T> {
+        SDRClassifier::create(steps, alpha, act_value_alpha, column_size, mode).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /**
+     * Same as `new` but invalid parameters are reported as an error, including an `alpha`
+     * that is not positive, an `act_value_alpha` outside `[0, 1]` and duplicate steps.
+     */
+    pub fn try_new(steps: Vec<usize>,
+                   alpha: f32,
+                   act_value_alpha: f32,
+                   column_size: usize)
+                   -> Result<SDRClassifier<T>, ClassifierError> {
+        SDRClassifier::try_new_with_mode(steps, alpha, act_value_alpha, column_size, ClassifierMode::Squared)
+    }
+
+    pub fn try_new_with_mode(steps: Vec<usize>,
+                             alpha: f32,
+                             act_value_alpha: f32,
+                             column_size: usize,
+                             mode: ClassifierMode)
+                             -> Result<SDRClassifier<T>, ClassifierError> {
+        for (index, &step) in steps.iter().enumerate() {
+            if steps[..index].contains(&step) {
+                return Err(ClassifierError::DuplicateStep(step));
+            }
+        }
+        if alpha.is_nan() || alpha <= 0.0 {
+            return Err(ClassifierError::InvalidAlpha(alpha));
+        }
+        if !(0.0..=1.0).contains(&act_value_alpha) {
+            return Err(ClassifierError::ActValueAlphaOutOfRange(act_value_alpha));
+        }
+        SDRClassifier::create(steps, alpha, act_value_alpha, column_size, mode)
+    }
+
+    /** Only rejects what the classifier can not be built without, see `new`. */
+    fn create(steps: Vec<usize>,
+              alpha: f32,
+              act_value_alpha: f32,
+              column_size: usize,
+              mode: ClassifierMode)
+              -> Result<SDRClassifier<T>, ClassifierError> {
+        if steps.is_empty() {
+            return Err(ClassifierError::NoSteps);
+        }
+        if column_size == 0 {
+            return Err(ClassifierError::EmptyInput);
+        }
+        let history_size = *steps.iter().max().unwrap() + 1;
         let len = steps.len();
-        SDRClassifier {
+        Ok(SDRClassifier {
             steps: steps,
             alpha: alpha,
             act_value_alpha: act_value_alpha,
             actual_values: vec![None],
-            pattern_history: VecDeque::with_capacity(max),
+            averages: vec![None],
+            pattern_history: VecDeque::with_capacity(history_size),
+            history_size,
             record_num_minus_learn_iteration: -1,
             max_bucket_idx: 0,
             max_input_idx: column_size - 1,
             learn_iteration: 0,
-            weight_matrix: vec![vec![vec![0f32; column_size]; 1]; len],
+            weight_matrix: vec![FnvHashMap::default(); len],
             error: Vec::with_capacity(10),
             infer: vec![(0, Vec::new());len],
-            infer_empty: vec![(0, Vec::new());len],
+            mode,
+            label_threshold: None,
+            bucket_thresholds: Vec::new(),
+        })
+    }
+
+    pub fn mode(&self) -> ClassifierMode {
+        self.mode
+    }
+
+    /** Sets the label threshold of the buckets without their own threshold. */
+    pub fn set_label_threshold(&mut self, threshold: f32) -> Result<(), ClassifierError> {
+        if !(0.0..=1.0).contains(&threshold) {
+            return Err(ClassifierError::ThresholdOutOfRange(threshold));
         }
+        self.label_threshold = Some(threshold);
+        Ok(())
+    }
+
+    /** Sets the label threshold of `bucket`, rare labels usually need a lower one. */
+    pub fn set_bucket_threshold(&mut self, bucket: usize, threshold: f32) -> Result<(), ClassifierError> {
+        if !(0.0..=1.0).contains(&threshold) {
+            return Err(ClassifierError::ThresholdOutOfRange(threshold));
+        }
+        if bucket >= self.bucket_thresholds.len() {
+            self.bucket_thresholds.resize(bucket + 1, None);
+        }
+        self.bucket_thresholds[bucket] = Some(threshold);
+        Ok(())
+    }
+
+    /** The probability above which `bucket` is predicted as a label. */
+    pub fn label_threshold(&self, bucket: usize) -> f32 {
+        self.bucket_thresholds.get(bucket)
+            .and_then(|&threshold| threshold)
+            .or(self.label_threshold)
+            .unwrap_or(1.0 / (self.max_bucket_idx + 1) as f32)
+    }
+
+    /** The number of input bits seen so far, at least the `column_size` of the constructor. */
+    pub fn input_width(&self) -> usize {
+        self.max_input_idx + 1
     }
 
     pub fn get_value(&self, bucket: usize) -> Option<T> {
-        self.actual_values[bucket].clone()
+        self.actual_values.get(bucket).and_then(|value| value.clone())
+    }
+
+    /**
+     * The actual values of the buckets weighted by `distribution`, one of the
+     * distributions returned by `compute`. Buckets without a value are ignored.
+     */
+    pub fn weighted_value(&self, distribution: &[f32]) -> Option<T> {
+        let values: Vec<(T, f32)> = distribution.iter()
+            .enumerate()
+            .filter_map(|(bucket, &probability)| self.get_value(bucket).map(|value| (value, probability)))
+            .collect();
+        T::weighted_mean(&values)
     }
 
     /**
@@ -124,52 +409,49 @@ impl<T> SDRClassifier<T>
 	 * @param learn <p>
 	 * If true, learn this sample.
 	 * @param infer <p>
-	 * If true, perform inference. If false, null will be returned.
+	 * If true, perform inference. If false, the result has no distributions.
 	 * 
 	 * @return
-	 * {@link Classification} containing inference results if {@code learn} param is true,
-	 * otherwise, will return {@code null}. The Classification
-	 * contains the computed probability distribution (relative likelihood for each
-	 * bucketIdx starting from bucketIdx 0) for each step in {@code steps}. Each bucket's
-	 * likelihood can be accessed individually, or all the buckets' likelihoods can
-	 * be obtained in the form of a double array.
+	 * A {@link Classification} with the computed probability distribution (relative
+	 * likelihood for each bucketIdx starting from bucketIdx 0) for each step in {@code steps}
+	 * if {@code infer} is true, no distributions otherwise. Each bucket's likelihood
+	 * can be accessed individually, or all the buckets' likelihoods as a slice.
 	 *
- 	 * <pre>{@code
- 	 * //Get likelihood val for bucket 0, 5 steps in future
-	 * classification.getStat(5, 0);
+	 * <pre>{@code
+	 * //Get likelihood val for bucket 0, 5 steps in future
+	 * classification.stat(5, 0);
 	 *
- 	 * //Get all buckets' likelihoods as double[] where each
-	 * //index is the likelihood for that bucket
-	 * //(e.g. [0] contains likelihood for bucketIdx 0)
-	 * classification.getStats(5);
+	 * //Get all buckets' likelihoods, where each index is the likelihood
+	 * //for that bucket (e.g. [0] contains likelihood for bucketIdx 0)
+	 * classification.stats(5);
+	 *
+	 * //Get the 3 most likely buckets with their likelihood
+	 * classification.top_k(5, 3);
 	 * }</pre>
 	 *
-	 * The Classification also contains the average actual value for each bucket.
-	 * The average values for the buckets can be accessed individually, or altogether
-	 * as a double[].
+	 * The Classification also contains the actual value of each bucket.
 	 *
 	 * <pre>{@code
-	 * //Get average actual val for bucket 0
-	 * classification.getActualValue(0);
+	 * //Get actual val for bucket 0
+	 * classification.actual_value(0);
 	 *
-	 * //Get average vals for all buckets as double[], where
-	 * //each index is the average val for that bucket
-	 * //(e.g. [0] contains average val for bucketIdx 0)
-	 * classification.getActualValues();
+	 * //Get vals for all buckets, where each index is the val for that bucket
+	 * classification.actual_values;
 	 * }</pre>
 	 *
 	 * The Classification can also be queried for the most probable bucket (the bucket
-	 * with the highest associated likelihood value), as well as the average input value
+	 * with the highest associated likelihood value), as well as the actual value
 	 * that corresponds to that bucket.
 	 *
 	 * <pre>{@code
-	 * //Get index of most probable bucket
-	 * classification.getMostProbableBucketIndex();
+	 * //Get index of most probable bucket 5 steps in future
+	 * classification.most_probable_bucket(5);
 	 *
-	 * //Get the average actual val for that bucket
-	 * classification.getMostProbableValue();
+	 * //Get the actual val for that bucket
+	 * classification.most_probable_value(5);
 	 * }</pre>
 	 *
+     * Panics when `record_num` is below the previous record number, see `try_compute`.
 	 */
     pub fn compute(&mut self,
                    record_num: u32,
@@ -178,7 +460,68 @@ impl<T> SDRClassifier<T>
                    pattern: &[usize],
                    learn: bool,
                    infer: bool)
-                   -> &Vec<(u8, Vec<f32>)> {
+                   -> Classification<T> {
+        match self.try_compute(record_num, bucket_idx, act_value, pattern, learn, infer) {
+            Ok(result) => result,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /**
+     * Same as `compute` but a record number lower than the previous one is reported
+     * as an error and leaves the classifier untouched.
+     */
+    pub fn try_compute(&mut self,
+                       record_num: u32,
+                       bucket_idx: usize,
+                       act_value: T,
+                       pattern: &[usize],
+                       learn: bool,
+                       infer: bool)
+                       -> Result<Classification<T>, ClassifierError> {
+        self.try_compute_multi(record_num, &[(bucket_idx, act_value)], pattern, learn, infer)
+    }
+
+    /**
+     * Same as `compute` for a record with several labels, each one a bucket with its
+     * actual value. The target probability is shared equally by the labels, a record
+     * without labels does not learn. `Classification::labels` gives the buckets whose
+     * probability is above their `label_threshold`. Panics like `compute` and when a
+     * bucket is listed twice.
+     */
+    pub fn compute_multi(&mut self,
+                         record_num: u32,
+                         labels: &[(usize, T)],
+                         pattern: &[usize],
+                         learn: bool,
+                         infer: bool)
+                         -> Classification<T> {
+        match self.try_compute_multi(record_num, labels, pattern, learn, infer) {
+            Ok(result) => result,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /** Same as `compute_multi` but invalid input is reported as an error, see `try_compute`. */
+    pub fn try_compute_multi(&mut self,
+                             record_num: u32,
+                             labels: &[(usize, T)],
+                             pattern: &[usize],
+                             learn: bool,
+                             infer: bool)
+                             -> Result<Classification<T>, ClassifierError> {
+        for (index, &(bucket, _)) in labels.iter().enumerate() {
+            if labels[..index].iter().any(|&(other, _)| other == bucket) {
+                return Err(ClassifierError::DuplicateBucket(bucket));
+            }
+        }
+        if self.record_num_minus_learn_iteration != -1 {
+            let previous = (self.learn_iteration as i32 + self.record_num_minus_learn_iteration) as u32;
+            if record_num < previous {
+                return Err(ClassifierError::RecordOutOfOrder { record_num, previous });
+            }
+        }
+
         // Classification<T> retVal = null;
         //List<T> actualValues = (List<T>)this.actualValues;
 
@@ -190,8 +533,13 @@ impl<T> SDRClassifier<T>
         //Update the learn iteration
         self.learn_iteration = (record_num as i32 - self.record_num_minus_learn_iteration) as u32;
 
+        //Update maxInputIdx, the weight rows of new bits are created when they learn
+        if let Some(&index) = pattern.iter().max() {
+            self.max_input_idx = cmp::max(self.max_input_idx, index);
+        }
+
         //Store pattern in our history
-        if self.pattern_history.len() == self.pattern_history.capacity() {
+        if self.pattern_history.len() == self.history_size {
             self.pattern_history.pop_back();
         }
         self.pattern_history
@@ -205,16 +553,10 @@ impl<T> SDRClassifier<T>
 
         //------------------------------------------------------------------------
         //Learning:
-        if learn {
-            // Update maxBucketIndex and augment weight matrix with zero padding
-            if bucket_idx > self.max_bucket_idx {
-                for &steps in &self.steps {
-                    for i in self.max_bucket_idx..bucket_idx {
-                        self.weight_matrix[steps as usize].push(vec![0.0; self.max_input_idx + 1]);
-                    }
-                }
-                self.max_bucket_idx = bucket_idx;
-            }
+        if learn && !labels.is_empty() {
+            // Update maxBucketIndex, the weight rows are padded when they learn
+            let bucket_idx = labels.iter().map(|&(bucket, _)| bucket).max().unwrap();
+            self.max_bucket_idx = cmp::max(self.max_bucket_idx, bucket_idx);
 
 
             // Update rolling average of actual values if it's a scalar. If it's not, it
@@ -222,21 +564,31 @@ impl<T> SDRClassifier<T>
             // we don't need a running average.
             while self.max_bucket_idx > self.actual_values.len() - 1 {
                 self.actual_values.push(None);
+                self.averages.push(None);
             }
 
 
-            if self.actual_values[bucket_idx].is_none() {
-                self.actual_values[bucket_idx] = Some(act_value);
-            } else {
-                // let value = self.actual_values[bucket_idx].unwrap();
-                //TODO: find a generic way to deal with numbers
-                /*
-                Double val = ((1.0 - actValueAlpha) * ((Number)actualValues.get(bucketIdx)).doubleValue() +
-							actValueAlpha * ((Number)actValue).doubleValue());
-					actualValues.set(bucketIdx, (T)val);
-                */
-                self.actual_values[bucket_idx] = Some(act_value);
+            for &(bucket, ref act_value) in labels {
+                // numbers that convert back from `f64` are averaged without rounding
+                let number = act_value.as_f64().and_then(|number| T::from_f64(number).map(|_| number));
+                let value = match (number, self.averages[bucket]) {
+                    (Some(number), Some(average)) => {
+                        let average = average.blend(&number, self.act_value_alpha);
+                        self.averages[bucket] = Some(average);
+                        T::from_f64(average).unwrap()
+                    }
+                    (Some(number), None) => {
+                        self.averages[bucket] = Some(number);
+                        act_value.clone()
+                    }
+                    (None, _) => match self.actual_values[bucket] {
+                        Some(ref current) => current.blend(act_value, self.act_value_alpha),
+                        None => act_value.clone(),
+                    },
+                };
+                self.actual_values[bucket] = Some(value);
             }
+            let target = 1.0 / labels.len() as f32;
 
             self.error.clear();
             let cap = self.error.capacity();
@@ -249,16 +601,20 @@ impl<T> SDRClassifier<T>
 
             for &(ref iter, ref pattern) in &self.pattern_history {
                 let nSteps = (self.learn_iteration - iter) as usize;
-                let nstps = nSteps as u8;
-                if self.steps.contains(&nstps) {
-                    Self::infer_single_step(&self.weight_matrix[nSteps], &pattern, nSteps, &mut self.error);
-                    for (index, val) in self.error.iter_mut().enumerate() {
-                        *val = ((index == bucket_idx) as usize) as f32 - *val;
+                if let Some(position) = self.steps.iter().position(|&step| step == nSteps) {
+                    Self::infer_step(self.mode, &self.weight_matrix[position], &pattern, nSteps, &mut self.error);
+                    for val in self.error.iter_mut() {
+                        *val = -*val;
+                    }
+                    for &(bucket, _) in labels {
+                        self.error[bucket] += target;
                     }
-                    for (index, matrix) in self.weight_matrix[nSteps].iter_mut().enumerate() {
-                        for &bit in pattern {
-                            matrix[bit] += self.alpha * self.error[index];
-                            //matrix[bit].clip(-1.0, 1.0); not sure if needed
+                    for &bit in pattern {
+                        let row = self.weight_matrix[position].entry(bit).or_default();
+                        row.resize(self.max_bucket_idx + 1, 0.0);
+                        for (weight, error) in row.iter_mut().zip(self.error.iter()) {
+                            *weight += self.alpha * error;
+                            //weight.clip(-1.0, 1.0); not sure if needed
                         }
                     }
                 }
@@ -266,37 +622,47 @@ impl<T> SDRClassifier<T>
         }
 
 
-        if infer {
-            &self.infer
-        } else {
-            &self.infer_empty
-        }
+        let distributions = if infer { self.infer.clone() } else { Vec::new() };
+        let thresholds = (0..self.max_bucket_idx + 1).map(|bucket| self.label_threshold(bucket)).collect();
+        Ok(Classification::new(distributions, self.actual_values.clone(), thresholds))
     }
 
     pub fn infer(&mut self, pattern: &[usize]) {
-        for (step,infer) in self.steps.iter().zip(self.infer.iter_mut()) {
+        for ((step, matrix), infer) in self.steps.iter().zip(self.weight_matrix.iter()).zip(self.infer.iter_mut()) {
             infer.1.clear();
-            let cap = infer.1.capacity();
-            if self.max_bucket_idx + 1 > cap {
-                infer.1.reserve(self.max_bucket_idx + 1 - cap);
-            }
-            for _ in 0..infer.1.capacity() {
-                infer.1.push(0.0);
-            }
+            infer.1.resize(self.max_bucket_idx + 1, 0.0);
 
-            Self::infer_single_step(&self.weight_matrix[*step as usize], pattern, *step as usize, &mut infer.1[..]);
+            Self::infer_step(self.mode, matrix, pattern, *step, &mut infer.1[..]);
             infer.0 = *step;
         }
     }
 
-    pub fn infer_single_step(matrix: &Vec<Vec<f32>>, pattern: &[usize], step: usize, into: &mut [f32]) {
-        // Compute the output activation "level" for each bucket (matrix row)
-        // we've seen so far and store in double[]
-        for (index, val) in into.iter_mut().enumerate() {
+    fn infer_step(mode: ClassifierMode, matrix: &FnvHashMap<usize, Vec<f32>>, pattern: &[usize], step: usize, into: &mut [f32]) {
+        match mode {
+            ClassifierMode::Squared => Self::infer_single_step(matrix, pattern, step, into),
+            ClassifierMode::Softmax => Self::infer_single_step_softmax(matrix, pattern, step, into),
+        }
+    }
+
+    /** Sums the weights of the active bits per bucket into `into`. */
+    fn activations(matrix: &FnvHashMap<usize, Vec<f32>>, pattern: &[usize], into: &mut [f32]) {
+        for val in into.iter_mut() {
             *val = 0.0;
-            for &pattern_value in pattern {
-                *val += matrix[index][pattern_value];
+        }
+        for bit in pattern {
+            if let Some(row) = matrix.get(bit) {
+                for (val, weight) in into.iter_mut().zip(row.iter()) {
+                    *val += weight;
+                }
             }
+        }
+    }
+
+    pub fn infer_single_step(matrix: &FnvHashMap<usize, Vec<f32>>, pattern: &[usize], _step: usize, into: &mut [f32]) {
+        // Compute the output activation "level" for each bucket (matrix row)
+        // we've seen so far and store in double[]
+        Self::activations(matrix, pattern, into);
+        for val in into.iter_mut() {
             if *val < 0.001 {
                 *val = 0.0;
             } else {
@@ -315,4 +681,31 @@ impl<T> SDRClassifier<T>
             }
         }
     }
+
+    /**
+     * Softmax of the summed weights of the active bits. The largest activation is
+     * subtracted before `exp` so large weights can not overflow, non finite sums
+     * fall back to a uniform distribution.
+     */
+    pub fn infer_single_step_softmax(matrix: &FnvHashMap<usize, Vec<f32>>, pattern: &[usize], _step: usize, into: &mut [f32]) {
+        Self::activations(matrix, pattern, into);
+
+        let max = into.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+        let mut sum = 0.0;
+        for val in into.iter_mut() {
+            *val = (*val - max).exp();
+            sum += *val;
+        }
+
+        if sum.is_finite() && sum > 0.0 {
+            for val in into.iter_mut() {
+                *val /= sum;
+            }
+        } else {
+            let uniform = 1.0 / into.len() as f32;
+            for val in into.iter_mut() {
+                *val = uniform;
+            }
+        }
+    }
 }
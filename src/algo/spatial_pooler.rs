@@ -4,7 +4,9 @@ use std;
 use std::ops::{Index, Range};
 use std::option::Option;
 use std::cmp;
-use algo::{PotentialPool, Topology};
+use std::fmt;
+use algo::{PotentialPool, Topology, SpatialPoolerBuilder};
+use algo::observer::{LearningObserver, notify};
 use rand::{Rng, XorShiftRng, SeedableRng};
 use collect_slice::CollectSlice;
 use util::universal_rand::*;
@@ -15,12 +17,15 @@ use rayon::prelude::*;
 
 pub struct SpatialPooler {
     pub rand: UniversalRng,
+    /** The seed `rand` was created from, `rand` itself moves on with every draw. */
+    pub seed: u32,
     pub potential: PotentialPool,
 
     pub iteration_num: u32,
     pub iteration_learn_num: u32,
 
     pub inhibition_radius: usize,
+    /** Input distance a column samples its potential synapses from, `-1` is the whole input. */
     pub potential_radius: i32,
     pub potential_pct: f64,
     pub global_inhibition: bool,
@@ -63,6 +68,9 @@ pub struct SpatialPooler {
     pub tie_broken_overlaps: Vec<f32>,
     //pub kdtree: KdTree<usize>,
     pub compability_mode: bool,
+
+    /** Receives boost, bump and proximal synapse events while learning, see `LearningObserver`. */
+    pub observer: Option<Box<dyn LearningObserver + Send>>,
 }
 
 pub struct SynapsePermenenceOptions {
@@ -77,11 +85,87 @@ pub struct SynapsePermenenceOptions {
 
 
 
+#[derive(Debug,Clone,PartialEq)]
 pub enum SPError {
-    InvalidInhibitionParameters,
+    /** Neither `num_active_columns_per_inh_area` nor a `local_area_density` in `(0, 0.5]` is set. */
+    InvalidInhibitionParameters { num_active_columns_per_inh_area: f64, local_area_density: f64 },
+    /** The input or the column dimensions are empty or contain a zero. */
+    EmptyDimensions { input_dimensions: Vec<usize>, column_dimensions: Vec<usize> },
+    /** Columns are mapped onto the input dimension by dimension, both need as many. */
+    DimensionMismatch { input_dimensions: usize, column_dimensions: usize },
+    /** `potential_pct` must be in `(0, 1]`. */
+    PotentialPctOutOfRange(f64),
+    /** `potential_radius` must not be negative, except for `-1` which covers the whole input. */
+    NegativePotentialRadius(i32),
+    /** `init_connected_pct` must be in `[0, 1]`. */
+    InitConnectedPctOutOfRange(f32),
+    /** The permanence bounds need `min < max`. */
+    InvalidPermanenceBounds { min: f32, max: f32 },
+    /** The connected permanence must be within the permanence bounds. */
+    ConnectedOutOfBounds { connected: f32, min: f32, max: f32 },
+    /** `active_inc` and `inactive_dec` are amounts and can not be negative. */
+    NegativePermanenceChange { active_inc: f32, inactive_dec: f32 },
+    /** `duty_cycle_period` and `update_period` must be at least 1. */
+    ZeroPeriod { duty_cycle_period: u32, update_period: u32 },
+    /** Boosting can only raise the overlap of weak columns, `max_boost` must be at least 1. */
+    MaxBoostBelowOne(f32),
+    /** `compute` was called before `init`. */
+    NotInitialized,
+    InputSizeMismatch { expected: usize, actual: usize },
+}
+
+impl fmt::Display for SPError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SPError::InvalidInhibitionParameters { num_active_columns_per_inh_area, local_area_density } =>
+                write!(f, "set num_active_columns_per_inh_area ({}) above 0 or local_area_density ({}) in (0, 0.5]", num_active_columns_per_inh_area, local_area_density),
+            SPError::EmptyDimensions { ref input_dimensions, ref column_dimensions } =>
+                write!(f, "input dimensions {:?} and column dimensions {:?} must be non empty and non zero", input_dimensions, column_dimensions),
+            SPError::DimensionMismatch { input_dimensions, column_dimensions } =>
+                write!(f, "{} input dimensions can not be mapped onto {} column dimensions", input_dimensions, column_dimensions),
+            SPError::PotentialPctOutOfRange(pct) => write!(f, "potential_pct {} is not in (0, 1]", pct),
+            SPError::NegativePotentialRadius(radius) => write!(f, "potential_radius {} is negative", radius),
+            SPError::InitConnectedPctOutOfRange(pct) => write!(f, "init_connected_pct {} is not in [0, 1]", pct),
+            SPError::InvalidPermanenceBounds { min, max } => write!(f, "permanence min {} must be below max {}", min, max),
+            SPError::ConnectedOutOfBounds { connected, min, max } => write!(f, "connected permanence {} is not in [{}, {}]", connected, min, max),
+            SPError::NegativePermanenceChange { active_inc, inactive_dec } =>
+                write!(f, "active_inc {} and inactive_dec {} must not be negative", active_inc, inactive_dec),
+            SPError::ZeroPeriod { duty_cycle_period, update_period } =>
+                write!(f, "duty_cycle_period {} and update_period {} must be at least 1", duty_cycle_period, update_period),
+            SPError::MaxBoostBelowOne(boost) => write!(f, "max_boost {} is below 1", boost),
+            SPError::NotInitialized => write!(f, "the spatial pooler must be initialized before compute"),
+            SPError::InputSizeMismatch { expected, actual } => write!(f, "the spatial pooler has {} inputs, got {}", expected, actual),
+        }
+    }
+}
+
+impl std::error::Error for SPError {
+    fn description(&self) -> &str {
+        match *self {
+            SPError::InvalidInhibitionParameters { .. } => "invalid inhibition parameters",
+            SPError::EmptyDimensions { .. } => "empty dimensions",
+            SPError::DimensionMismatch { .. } => "dimension mismatch",
+            SPError::PotentialPctOutOfRange(_) => "potential_pct out of range",
+            SPError::NegativePotentialRadius(_) => "negative potential_radius",
+            SPError::InitConnectedPctOutOfRange(_) => "init_connected_pct out of range",
+            SPError::InvalidPermanenceBounds { .. } => "invalid permanence bounds",
+            SPError::ConnectedOutOfBounds { .. } => "connected permanence out of bounds",
+            SPError::NegativePermanenceChange { .. } => "negative permanence change",
+            SPError::ZeroPeriod { .. } => "zero period",
+            SPError::MaxBoostBelowOne(_) => "max_boost below 1",
+            SPError::NotInitialized => "spatial pooler not initialized",
+            SPError::InputSizeMismatch { .. } => "input size mismatch",
+        }
+    }
 }
 
 impl SpatialPooler {
+    /**
+     * An uninitialized pooler with the default parameters, to be changed through the public
+     * fields and then initialized with `init`. Nothing is validated before `init`, and
+     * fields changed after it are never validated; `try_compute` only detects a pooler
+     * that was not initialized.
+     */
     pub fn new(input_dimensions: Vec<usize>, column_dimensions: Vec<usize>) -> SpatialPooler {
         let column_size = column_dimensions.iter().product::<usize>();
         let input_size = input_dimensions.iter().product::<usize>();
@@ -137,37 +221,115 @@ impl SpatialPooler {
 
             potential: PotentialPool::new(0, 0),
             rand: UniversalRng::from_seed([42, 0, 0, 0]),
+            seed: 42,
 
             overlaps: vec![0.0; column_size],
             winner_columns: vec![0; column_size],
             tie_broken_overlaps: vec![0.0; column_size],
             compability_mode: false,
+            observer: None,
         };
         c
     }
 
 
+    /** A `SpatialPoolerBuilder`, which validates the parameters and returns an initialized pooler. */
+    pub fn builder(input_dimensions: Vec<usize>, column_dimensions: Vec<usize>) -> SpatialPoolerBuilder {
+        SpatialPoolerBuilder::new(input_dimensions, column_dimensions)
+    }
+
     pub fn post_init(&mut self) {
         self.syn_perm_options.below_stimulus_inc = self.syn_perm_options.connected / 10.0;
         self.syn_perm_options.trim_threshold = self.syn_perm_options.active_inc / 2.0;
+    }
+
+    /** `potential_radius` with `-1` resolved to the whole input. */
+    fn effective_potential_radius(&self) -> usize {
         if self.potential_radius == -1 {
-            self.potential_radius = self.num_inputs as i32;
+            self.num_inputs
+        } else {
+            self.potential_radius as usize
         }
     }
 
-    pub fn init(&mut self) -> Option<SPError> {
-        if self.num_active_columns_per_inh_area == 0.0 &&
-           (self.local_area_density == 0.0 || self.local_area_density > 0.5) {
-            Some(SPError::InvalidInhibitionParameters)
-        } else {
-            self.post_init();
-            self.gen_potential();
-            self.connect_and_configure_inputs();
-            None
+    /** Checks every parameter, returns the first invalid one. */
+    pub fn validate(&self) -> Result<(), SPError> {
+        if self.input_dimensions.is_empty() || self.column_dimensions.is_empty() ||
+           self.input_dimensions.contains(&0) || self.column_dimensions.contains(&0) {
+            return Err(SPError::EmptyDimensions {
+                input_dimensions: self.input_dimensions.clone(),
+                column_dimensions: self.column_dimensions.clone(),
+            });
+        }
+        if self.input_dimensions.len() != self.column_dimensions.len() {
+            return Err(SPError::DimensionMismatch {
+                input_dimensions: self.input_dimensions.len(),
+                column_dimensions: self.column_dimensions.len(),
+            });
+        }
+        if !(self.potential_pct > 0.0 && self.potential_pct <= 1.0) {
+            return Err(SPError::PotentialPctOutOfRange(self.potential_pct));
+        }
+        if self.potential_radius < -1 {
+            return Err(SPError::NegativePotentialRadius(self.potential_radius));
+        }
+        if !(self.init_connected_pct >= 0.0 && self.init_connected_pct <= 1.0) {
+            return Err(SPError::InitConnectedPctOutOfRange(self.init_connected_pct));
+        }
+        let options = &self.syn_perm_options;
+        if options.min.is_nan() || options.max.is_nan() || options.min >= options.max {
+            return Err(SPError::InvalidPermanenceBounds { min: options.min, max: options.max });
+        }
+        if !(options.connected >= options.min && options.connected <= options.max) {
+            return Err(SPError::ConnectedOutOfBounds { connected: options.connected, min: options.min, max: options.max });
+        }
+        if !(options.active_inc >= 0.0 && options.inactive_dec >= 0.0) {
+            return Err(SPError::NegativePermanenceChange { active_inc: options.active_inc, inactive_dec: options.inactive_dec });
         }
+        if self.duty_cycle_period == 0 || self.update_period == 0 {
+            return Err(SPError::ZeroPeriod { duty_cycle_period: self.duty_cycle_period, update_period: self.update_period });
+        }
+        if self.max_boost.is_nan() || self.max_boost < 1.0 {
+            return Err(SPError::MaxBoostBelowOne(self.max_boost));
+        }
+        if !(self.num_active_columns_per_inh_area > 0.0 ||
+             (self.local_area_density > 0.0 && self.local_area_density <= 0.5)) {
+            return Err(SPError::InvalidInhibitionParameters {
+                num_active_columns_per_inh_area: self.num_active_columns_per_inh_area,
+                local_area_density: self.local_area_density,
+            });
+        }
+        Ok(())
+    }
+
+    /**
+     * Validates the parameters, then builds the potential pools and connects the columns.
+     * The pooler is left untouched when a parameter is invalid.
+     */
+    #[must_use = "an invalid pooler stays uninitialized"]
+    pub fn init(&mut self) -> Result<(), SPError> {
+        self.validate()?;
+        self.post_init();
+        self.gen_potential();
+        self.connect_and_configure_inputs();
+        Ok(())
     }
 
+    /** Feeds `input_vector`, which must have `num_inputs` bits. Panics otherwise, see `try_compute`. */
     pub fn compute(&mut self, input_vector: &[bool], learn: bool) {
+        if let Err(err) = self.try_compute(input_vector, learn) {
+            panic!("{}", err);
+        }
+    }
+
+    /** Same as `compute` but an uninitialized pooler or an input of the wrong size is an error. */
+    pub fn try_compute(&mut self, input_vector: &[bool], learn: bool) -> Result<(), SPError> {
+        if self.potential.num_columns() != self.num_columns {
+            return Err(SPError::NotInitialized);
+        }
+        if input_vector.len() != self.num_inputs {
+            return Err(SPError::InputSizeMismatch { expected: self.num_inputs, actual: input_vector.len() });
+        }
         self.update_iteration_number(learn);
         self.calculate_overlaps(input_vector);
         self.boost(learn);
@@ -183,6 +345,7 @@ impl SpatialPooler {
                 self.update_min_duty_cycles();
             }
         }
+        Ok(())
     }
 
     pub fn adapt_groups(&mut self) {
@@ -191,6 +354,10 @@ impl SpatialPooler {
 
     pub fn adapt_synapses(&mut self, input_vector: &[bool]) {
         for column in &self.winner_columns {
+            let connected_before = match self.observer {
+                Some(_) => Some(self.potential.connected_inputs(*column)),
+                None => None,
+            };
             for val in self.potential
                     .connections_by_column_mut(*column)
                     .iter_mut() {
@@ -205,6 +372,9 @@ impl SpatialPooler {
                                                true,
                                                (self.stimulus_threshold + 0.5) as i32,
                                                &self.syn_perm_options);
+            if let Some(before) = connected_before {
+                notify_connection_changes(&self.potential, &mut self.observer, *column, &before);
+            }
         }
     }
 
@@ -239,6 +409,11 @@ impl SpatialPooler {
                 .enumerate()
                 .zip(self.min_overlap_duty_cycles.iter()) {
             if min_overlap_duty_cycle > overlap_duty_cycle {
+                notify(&mut self.observer, |o| o.column_bumped(column));
+                let connected_before = match self.observer {
+                    Some(_) => Some(self.potential.connected_inputs(column)),
+                    None => None,
+                };
                 for val in self.potential.connections_by_column_mut(column) {
                     val.permanence += self.syn_perm_options.below_stimulus_inc;
                 }
@@ -247,6 +422,9 @@ impl SpatialPooler {
                                                    true,
                                                    (self.stimulus_threshold + 0.5) as i32,
                                                    &self.syn_perm_options);
+                if let Some(before) = connected_before {
+                    notify_connection_changes(&self.potential, &mut self.observer, column, &before);
+                }
             }
         }
     }
@@ -260,17 +438,23 @@ impl SpatialPooler {
             }
         }
         if got_elements {
-            for ((boost, &min_active), &active) in
+            for (column, ((boost, &min_active), &active)) in
                 self.boost_factors
                     .iter_mut()
                     .zip(self.min_active_duty_cycles.iter())
-                    .zip(self.active_duty_cycles.iter()) {
+                    .zip(self.active_duty_cycles.iter())
+                    .enumerate() {
+                let old = *boost;
                 *boost = if active > min_active {
                     1.0
                 } else {
                     let ma = if min_active == 0.0 { 1.0 } else { min_active };
                     (((1.0 - self.max_boost) / ma) * active) + self.max_boost
                 };
+                if old != *boost {
+                    let new = *boost;
+                    notify(&mut self.observer, |o| o.boost_changed(column, old, new));
+                }
             }
         }
     }
@@ -494,7 +678,7 @@ impl SpatialPooler {
         let center_input = self.map_column(column_index);
         let elements_iter =
             self.input_topology
-                .neighborhood(center_input, self.potential_radius as usize, wrap_around);
+                .neighborhood(center_input, self.effective_potential_radius(), wrap_around);
         let (size, _) = elements_iter.size_hint();
         let final_size = self.potential_synapses(size);
 
@@ -560,7 +744,7 @@ impl SpatialPooler {
         self.potential_synapses(self.input_dimensions
                                     .iter()
                                     .fold(1usize, |acc, &dim| {
-            acc * cmp::min(dim, (self.potential_radius * 2) as usize)
+            acc * cmp::min(dim, self.effective_potential_radius() * 2)
         }))
     }
 
@@ -655,3 +839,18 @@ impl SpatialPooler {
         */
     }
 }
+
+/** Reports the proximal synapses of `column` whose connected state differs from `before`. */
+fn notify_connection_changes(potential: &PotentialPool, observer: &mut Option<Box<dyn LearningObserver + Send>>, column: usize, before: &[usize]) {
+    let after = potential.connected_inputs(column);
+    for &input in before {
+        if after.binary_search(&input).is_err() {
+            notify(observer, |o| o.proximal_connection_changed(column, input, false));
+        }
+    }
+    for &input in &after {
+        if before.binary_search(&input).is_err() {
+            notify(observer, |o| o.proximal_connection_changed(column, input, true));
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use algo::{SpatialPooler, SPError, LearningObserver};
+use util::UniversalRng;
+
+/**
+ * Collects the parameters of a `SpatialPooler` and only hands out a pooler once
+ * `SpatialPooler::validate` accepted them and it is initialized.
+ * Unset parameters keep the values of `SpatialPooler::new`. The fields of the built
+ * pooler stay public, changing them afterwards is not validated.
+ */
+pub struct SpatialPoolerBuilder {
+    sp: SpatialPooler,
+}
+
+impl SpatialPoolerBuilder {
+    pub fn new(input_dimensions: Vec<usize>, column_dimensions: Vec<usize>) -> SpatialPoolerBuilder {
+        SpatialPoolerBuilder {
+            sp: SpatialPooler::new(input_dimensions, column_dimensions),
+        }
+    }
+
+    pub fn potential_radius(mut self, radius: usize) -> SpatialPoolerBuilder {
+        self.sp.potential_radius = radius as i32;
+        self
+    }
+
+    /** Lets every column sample its potential synapses from the whole input. */
+    pub fn full_potential_radius(mut self) -> SpatialPoolerBuilder {
+        self.sp.potential_radius = -1;
+        self
+    }
+
+    pub fn potential_pct(mut self, pct: f64) -> SpatialPoolerBuilder {
+        self.sp.potential_pct = pct;
+        self
+    }
+
+    pub fn global_inhibition(mut self, global: bool) -> SpatialPoolerBuilder {
+        self.sp.global_inhibition = global;
+        self
+    }
+
+    pub fn local_area_density(mut self, density: f64) -> SpatialPoolerBuilder {
+        self.sp.local_area_density = density;
+        self
+    }
+
+    pub fn num_active_columns_per_inh_area(mut self, columns: f64) -> SpatialPoolerBuilder {
+        self.sp.num_active_columns_per_inh_area = columns;
+        self
+    }
+
+    pub fn stimulus_threshold(mut self, threshold: f32) -> SpatialPoolerBuilder {
+        self.sp.stimulus_threshold = threshold;
+        self
+    }
+
+    pub fn syn_perm_active_inc(mut self, inc: f32) -> SpatialPoolerBuilder {
+        self.sp.syn_perm_options.active_inc = inc;
+        self
+    }
+
+    pub fn syn_perm_inactive_dec(mut self, dec: f32) -> SpatialPoolerBuilder {
+        self.sp.syn_perm_options.inactive_dec = dec;
+        self
+    }
+
+    pub fn syn_perm_connected(mut self, connected: f32) -> SpatialPoolerBuilder {
+        self.sp.syn_perm_options.connected = connected;
+        self
+    }
+
+    pub fn syn_perm_bounds(mut self, min: f32, max: f32) -> SpatialPoolerBuilder {
+        self.sp.syn_perm_options.min = min;
+        self.sp.syn_perm_options.max = max;
+        self
+    }
+
+    pub fn min_pct_overlap_duty_cycles(mut self, pct: f32) -> SpatialPoolerBuilder {
+        self.sp.min_pct_overlap_duty_cycles = pct;
+        self
+    }
+
+    pub fn min_pct_active_duty_cycles(mut self, pct: f32) -> SpatialPoolerBuilder {
+        self.sp.min_pct_active_duty_cycles = pct;
+        self
+    }
+
+    pub fn duty_cycle_period(mut self, period: u32) -> SpatialPoolerBuilder {
+        self.sp.duty_cycle_period = period;
+        self
+    }
+
+    pub fn max_boost(mut self, boost: f32) -> SpatialPoolerBuilder {
+        self.sp.max_boost = boost;
+        self
+    }
+
+    pub fn wrap_around(mut self, wrap: bool) -> SpatialPoolerBuilder {
+        self.sp.wrap_around = wrap;
+        self
+    }
+
+    pub fn init_connected_pct(mut self, pct: f32) -> SpatialPoolerBuilder {
+        self.sp.init_connected_pct = pct;
+        self
+    }
+
+    pub fn update_period(mut self, period: u32) -> SpatialPoolerBuilder {
+        self.sp.update_period = period;
+        self
+    }
+
+    pub fn seed(mut self, seed: u32) -> SpatialPoolerBuilder {
+        self.sp.rand = UniversalRng::from_seed([seed, 0, 0, 0]);
+        self.sp.seed = seed;
+        self
+    }
+
+    pub fn compability_mode(mut self, compability: bool) -> SpatialPoolerBuilder {
+        self.sp.compability_mode = compability;
+        self
+    }
+
+    pub fn observer(mut self, observer: Box<dyn LearningObserver + Send>) -> SpatialPoolerBuilder {
+        self.sp.observer = Some(observer);
+        self
+    }
+
+    /** Validates the parameters and returns the initialized pooler. */
+    pub fn build(mut self) -> Result<SpatialPooler, SPError> {
+        self.sp.init()?;
+        Ok(self.sp)
+    }
+}
@@ -1,18 +1,22 @@
 use std;
 use std::collections::HashSet;
 use std::cmp;
+use std::fmt;
 use fnv::{FnvHashMap,FnvHashSet};
 use rand::{Rng, XorShiftRng, SeedableRng};
 use util::{UniversalRng, UniversalNext, PeekableWhile, PeekableWhileTrait};
 use util::numext::*;
+use algo::observer::{LearningObserver, notify};
 use quickersort;
+use rayon;
+use rayon::prelude::*;
 
 pub struct TemporalMemory {
-    pub active_cells: FnvHashSet<Cell>,
-    pub prev_active_cells: FnvHashSet<Cell>,
-
-    pub winner_cells: FnvHashSet<Cell>,
-    pub prev_winner_cells: FnvHashSet<Cell>,
+    /**
+     * Activity of the sequence currently fed to `compute`.
+     * Swap it with `swap_state` to run several streams through the same connections.
+     */
+    pub state: TMState,
 
     predictive_cells: FnvHashMap<Cell, u32>,
 
@@ -21,9 +25,6 @@ pub struct TemporalMemory {
 
     segments_am_helper: FnvHashMap<SegmentRef, (u32,u32)>,
 
-    pub segments_active: std::cell::Cell<Vec<SegmentScore>>,
-    pub segments_matching: std::cell::Cell<Vec<SegmentScore>>,
-
     empty_segments: Vec<Segment>,
     
     /**
@@ -42,6 +43,8 @@ pub struct TemporalMemory {
     pub max_new_synapse_count: u32, // = 20;
     /** The maximum number of segments (distal dendrites) allowed on a cell */
     pub max_segments_per_cell: u32, // = 255;
+    /** Which segment is replaced when a cell already has `max_segments_per_cell` segments */
+    pub segment_eviction: EvictionPolicy,
     /** The maximum number of synapses allowed on a given segment (distal dendrite) */
     pub max_synapses_per_segment: u32, // = 255;
     /** Initial permanence of a new synapse */
@@ -66,11 +69,172 @@ pub struct TemporalMemory {
     pub predicted_segment_decrement: f32,
 
     pub iteration: u64,
-    
 
+    /**
+     * Decay of segments that have not been active for a while, disabled when `None`.
+     */
+    pub forgetting: Option<ForgettingOptions>,
+    /** Everything removed by forgetting since the memory was created. */
+    pub pruned: PruneReport,
+
+    /**
+     * Spread `activate_dendrites` over the rayon thread pool.
+     * The counts are merged so results are identical to the serial path.
+     */
+    pub parallel_dendrites: bool,
+
+    /** Receives segment and synapse events while learning, see `LearningObserver`. */
+    pub observer: Option<Box<dyn LearningObserver + Send>>,
+
+    /** Number of columns, `compute` only accepts column indices below it. */
+    pub columns: u32,
+    /** Number of cells per column. */
     pub cells: u32,
 
     pub rand: UniversalRng,
+    /** The seed `rand` was created from, `rand` itself moves on with every draw. */
+    pub seed: u32,
+}
+
+/**
+ * The per sequence activity of a `TemporalMemory`, everything that is not a learned connection.
+ * A stream keeps its own state and hands it to `compute_with_state`, it can be saved with serde.
+ *
+ * `segments_active` and `segments_matching` point into the segments of the memory by slot.
+ * Learning on any other state can adapt, forget or evict those segments and reuse their
+ * slots, so a state stays valid only while every compute outside of it uses `learn=false`.
+ * `reset` a state that was set aside while the memory learned before computing it again.
+ */
+#[derive(Debug,Clone,Default,Serialize,Deserialize)]
+pub struct TMState {
+    pub active_cells: FnvHashSet<Cell>,
+    pub prev_active_cells: FnvHashSet<Cell>,
+
+    pub winner_cells: FnvHashSet<Cell>,
+    pub prev_winner_cells: FnvHashSet<Cell>,
+
+    /** Active cells that were predicted, i.e. not the result of a burst. */
+    pub predicted_active_cells: FnvHashSet<Cell>,
+
+    /**
+     * External input of the current step (e.g. a location signal), see `compute_external`.
+     * On the next step they join `prev_active_cells` and `prev_winner_cells`.
+     */
+    pub external_cells: FnvHashSet<Cell>,
+
+    pub segments_active: Vec<SegmentScore>,
+    pub segments_matching: Vec<SegmentScore>,
+}
+
+impl TMState {
+    pub fn new() -> TMState {
+        Default::default()
+    }
+
+    pub fn reset(&mut self) {
+        self.winner_cells.clear();
+        self.active_cells.clear();
+        self.predicted_active_cells.clear();
+        self.external_cells.clear();
+        self.segments_matching.clear();
+        self.segments_active.clear();
+    }
+}
+
+/**
+ * Chooses the segment to recycle when a full cell needs a new one.
+ * Empty segments are always reused first.
+ */
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Serialize,Deserialize)]
+pub enum EvictionPolicy {
+    /** The segment that was active the longest time ago. */
+    LeastRecentlyUsed,
+    /** The segment with the lowest sum of synapse permanences. */
+    LowestPermanence,
+    /** The segment with the fewest connected synapses. */
+    FewestConnected,
+    /** A uniformly random segment. */
+    Random,
+}
+
+impl EvictionPolicy {
+    pub fn select<R: Rng>(&self, segments: &[Segment], connected: f32, rand: &mut R) -> usize {
+        if let Some(index) = segments.iter().position(|seg| seg.synapses.is_empty()) {
+            return index;
+        }
+        let candidates = segments.iter().enumerate();
+        match *self {
+            EvictionPolicy::LeastRecentlyUsed => candidates.min_by_key(|&(_, seg)| seg.last_used).unwrap().0,
+            EvictionPolicy::LowestPermanence => candidates
+                .map(|(index, seg)| (index, seg.synapses.iter().map(|syn| syn.permanence).sum::<f32>()))
+                .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+                .unwrap().0,
+            EvictionPolicy::FewestConnected => candidates.min_by_key(|&(_, seg)| seg.num_connected_synapses(connected)).unwrap().0,
+            EvictionPolicy::Random => rand.next_uv_int(segments.len() as i32) as usize,
+        }
+    }
+}
+
+#[derive(Debug,Clone)]
+pub struct ForgettingOptions {
+    /** Forgetting runs every `period` learning iterations. */
+    pub period: u64,
+    /** Segments that were not active for this many iterations decay. */
+    pub unused_iterations: u64,
+    /** Amount subtracted from the permanence of every synapse of a decaying segment. */
+    pub permanence_decay: f32,
+}
+
+/**
+ * How much a forgetting pass removed, see `TemporalMemory::forget`.
+ */
+#[derive(Debug,Clone,Default,PartialEq)]
+pub struct PruneReport {
+    pub segments_decayed: usize,
+    pub synapses_removed: usize,
+    pub segments_removed: usize,
+}
+
+impl PruneReport {
+    pub fn add(&mut self, other: &PruneReport) {
+        self.segments_decayed += other.segments_decayed;
+        self.synapses_removed += other.synapses_removed;
+        self.segments_removed += other.segments_removed;
+    }
+}
+
+#[derive(Debug,Clone,PartialEq)]
+pub enum TMError {
+    ColumnOutOfRange { column: usize, columns: usize },
+    DuplicateColumn(usize),
+    UnsortedColumns { previous: usize, column: usize },
+    ExternalColumnInRange { column: usize, columns: usize },
+    /** A memory needs at least one column of one cell. */
+    ZeroSize { columns: u32, cells: u32 },
+}
+
+impl fmt::Display for TMError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TMError::ColumnOutOfRange { column, columns } => write!(f, "active column {} is out of range, the temporal memory has {} columns", column, columns),
+            TMError::DuplicateColumn(column) => write!(f, "active column {} appears more than once", column),
+            TMError::UnsortedColumns { previous, column } => write!(f, "active columns must be sorted, got {} after {}", column, previous),
+            TMError::ExternalColumnInRange { column, columns } => write!(f, "external cell column {} overlaps the {} columns of the temporal memory", column, columns),
+            TMError::ZeroSize { columns, cells } => write!(f, "a temporal memory of {} columns with {} cells each has no cells", columns, cells),
+        }
+    }
+}
+
+impl std::error::Error for TMError {
+    fn description(&self) -> &str {
+        match *self {
+            TMError::ColumnOutOfRange { .. } => "active column out of range",
+            TMError::DuplicateColumn(_) => "duplicate active column",
+            TMError::UnsortedColumns { .. } => "unsorted active columns",
+            TMError::ExternalColumnInRange { .. } => "external cell inside the temporal memory",
+            TMError::ZeroSize { .. } => "temporal memory without cells",
+        }
+    }
 }
 
 pub struct SynapseMap {
@@ -84,27 +248,114 @@ impl SynapseMap {
         }
     }
 
-    pub fn grow_synapses<R: Rng>(&mut self, segment: &mut Segment, segment_ref: &SegmentRef, active_cells: &FnvHashSet<Cell>, rand: &mut R, initial_permanence: f32, connected: f32, desired: u32, max_synapses: u32) 
+    pub fn grow_synapses<R: Rng>(&mut self, segment: &mut Segment, segment_ref: &SegmentRef, active_cells: &FnvHashSet<Cell>, rand: &mut R, initial_permanence: f32, connected: f32, desired: u32, max_synapses: u32, observer: &mut Option<Box<dyn LearningObserver + Send>>) 
     {
         let range = segment.grow_synapses(active_cells, initial_permanence, desired, rand);
-        self.add_synapses(segment, range, segment_ref, connected, max_synapses);
+        self.add_synapses(segment, range, segment_ref, connected, max_synapses, observer);
     }
 
-    pub fn add_synapses(&mut self, segment: &mut Segment, range: std::ops::Range<usize>, segment_ref: &SegmentRef, connected: f32, max_synapses: u32) 
+    pub fn add_synapses(&mut self, segment: &mut Segment, range: std::ops::Range<usize>, segment_ref: &SegmentRef, connected: f32, max_synapses: u32, observer: &mut Option<Box<dyn LearningObserver + Send>>) 
     {
-        let mut syns = &mut segment.synapses;
-        for (index, syn) in syns[range].iter().enumerate() {
+        let syns = &mut segment.synapses;
+        for syn in syns[range].iter() {
             self.map.entry(syn.cell).or_insert(FnvHashSet::default()).replace(SynapseLink{segment: segment_ref.clone(), connected: syn.permanence >= connected});
+            notify(observer, |o| o.synapse_grown(segment_ref, syn.cell, syn.permanence));
         }
 
-        //TODO: maybe sort and then remove, better for efficiency when syns.len() - max_synapses > log(syns.len())
-        while syns.len() > max_synapses as usize {
-            let index = { 
-                let (i,syn) = syns.iter().enumerate().min_by(|&(_,s), &(_,s2)| s.permanence.partial_cmp(&s2.permanence).unwrap()).unwrap();
-                self.map.get_mut(&syn.cell).unwrap().remove(&SynapseLink{segment: segment_ref.clone(), connected: false});
-                i
-            };
-            syns.swap_remove(index);
+        if syns.len() > max_synapses as usize {
+            self.trim_synapses(syns, segment_ref, syns.len() - max_synapses as usize, observer);
+        }
+    }
+
+    /**
+     * Removes the `count` synapses with the lowest permanence in one pass,
+     * the order of the remaining synapses is kept.
+     */
+    fn trim_synapses(&mut self, syns: &mut Vec<Synapse>, segment_ref: &SegmentRef, count: usize, observer: &mut Option<Box<dyn LearningObserver + Send>>) {
+        let mut order: Vec<usize> = (0..syns.len()).collect();
+        order.sort_by(|&a, &b| syns[a].permanence.partial_cmp(&syns[b].permanence).unwrap());
+
+        let mut remove = vec![false; syns.len()];
+        for &index in &order[..count] {
+            remove[index] = true;
+            self.map.get_mut(&syns[index].cell).unwrap().remove(&SynapseLink{segment: segment_ref.clone(), connected: false});
+            notify(observer, |o| o.synapse_destroyed(segment_ref, syns[index].cell));
+        }
+
+        let mut index = 0;
+        syns.retain(|_| {
+            index += 1;
+            !remove[index - 1]
+        });
+    }
+
+    /** Removes the reverse index entries of every synapse of `segment`. */
+    pub fn remove_links(&mut self, segment: &Segment, segment_ref: &SegmentRef, observer: &mut Option<Box<dyn LearningObserver + Send>>) {
+        for syn in &segment.synapses {
+            self.map.get_mut(&syn.cell).unwrap().remove(&SynapseLink{segment: segment_ref.clone(), connected: false});
+            notify(observer, |o| o.synapse_destroyed(segment_ref, syn.cell));
+        }
+    }
+
+    /**
+     * Counts, for every segment that has at least one synapse onto `active_cells`,
+     * the number of connected active synapses and the number of potential
+     * (any permanence) active synapses.
+     */
+    pub fn compute_activity(&self, active_cells: &FnvHashSet<Cell>, into: &mut FnvHashMap<SegmentRef, (u32,u32)>) {
+        for cell in active_cells.iter() {
+            match self.map.get(cell) {
+                Some(vec) => {
+                    for syn_link in vec {
+                       let val = into.entry(syn_link.segment.clone()).or_insert((0, 0));
+                       val.0 += syn_link.connected as u32;
+                       val.1 += 1;
+                    }
+                },
+                None => {},
+            }
+        }
+    }
+
+    /**
+     * Same as `compute_activity` but the presynaptic cells are partitioned across
+     * the rayon thread pool. Every chunk counts into its own map and the maps are
+     * summed afterwards, so the counts do not depend on scheduling.
+     */
+    pub fn compute_activity_parallel(&self, active_cells: &FnvHashSet<Cell>, into: &mut FnvHashMap<SegmentRef, (u32,u32)>) {
+        let cells: Vec<Cell> = active_cells.iter().cloned().collect();
+        let chunk_size = cmp::max(cells.len() / (rayon::current_num_threads() * 4), 64);
+
+        let counts = cells.par_chunks(chunk_size)
+            .map(|chunk| {
+                let mut counts: FnvHashMap<SegmentRef, (u32,u32)> = FnvHashMap::default();
+                for cell in chunk {
+                    if let Some(links) = self.map.get(cell) {
+                        for syn_link in links {
+                            let val = counts.entry(syn_link.segment.clone()).or_insert((0, 0));
+                            val.0 += syn_link.connected as u32;
+                            val.1 += 1;
+                        }
+                    }
+                }
+                counts
+            })
+            .reduce(FnvHashMap::default, |mut a, mut b| {
+                if a.len() < b.len() {
+                    std::mem::swap(&mut a, &mut b);
+                }
+                for (seg, val) in b {
+                    let sum = a.entry(seg).or_insert((0, 0));
+                    sum.0 += val.0;
+                    sum.1 += val.1;
+                }
+                a
+            });
+
+        for (seg, val) in counts {
+            let sum = into.entry(seg).or_insert((0, 0));
+            sum.0 += val.0;
+            sum.1 += val.1;
         }
     }
 }
@@ -129,18 +380,21 @@ impl SegmentContainer {
     }
 
 
-    pub fn insert(&mut self, synapse_map: &mut SynapseMap, segment: Segment, max_segments: u32) -> (&mut Segment, SegmentRef) {
+    pub fn insert<R: Rng>(&mut self, synapse_map: &mut SynapseMap, segment: Segment, max_segments: u32, policy: &EvictionPolicy, connected: f32, rand: &mut R, observer: &mut Option<Box<dyn LearningObserver + Send>>) -> (&mut Segment, SegmentRef) {
         let segments = self.map.entry(segment.cell).or_insert(Vec::new());
         if segments.len() >= max_segments as usize {
-            let index = {
-                let (index, seg) = segments.iter().enumerate().min_by_key(|&(_,seg)| seg.last_used * (seg.synapses.len() > 0) as u64).unwrap();
-                let seg_ref = SegmentRef{ cell: seg.cell, segment: index as u32};
-                for syn in &seg.synapses {
-                    synapse_map.map.get_mut(&syn.cell).unwrap().remove(&SynapseLink{segment: seg_ref.clone(), connected: false});
-                }
-                index
+            let (index, recycled) = {
+                let index = policy.select(segments, connected, rand);
+                let seg = &segments[index];
+                synapse_map.remove_links(seg, &SegmentRef{ cell: seg.cell, segment: index as u32}, observer);
+                (index, !seg.synapses.is_empty())
             };
             let segref = SegmentRef{ cell: segment.cell, segment: index as u32 };
+            if recycled {
+                notify(observer, |o| o.segment_recycled(&segref));
+            } else {
+                notify(observer, |o| o.segment_created(&segref));
+            }
             segments[index] = segment;
             (&mut segments[index], segref)
         } else {
@@ -151,11 +405,13 @@ impl SegmentContainer {
             match index_opt {
                 Some(index) => { 
                     let segref = SegmentRef{ cell: segment.cell, segment: index as u32 };
+                    notify(observer, |o| o.segment_created(&segref));
                     segments[index] = segment; 
                     (&mut segments[index], segref)
                 },
                 None => {
                     let segref = SegmentRef{ cell: segment.cell, segment: segments.len() as u32 };
+                    notify(observer, |o| o.segment_created(&segref));
                     segments.push(segment); 
                     (&mut segments[segref.segment as usize], segref)
                 },
@@ -231,7 +487,15 @@ impl Segment {
     fn grow_synapses<R: Rng>(&mut self, active_cells: &FnvHashSet<Cell>, initial_permanence: f32, desired: u32, rand: &mut R) -> std::ops::Range<usize> {
         let mut cells = active_cells.iter().cloned().collect::<Vec<Cell>>();
         cells.sort();
+        self.grow_synapses_from(&cells, initial_permanence, desired, rand)
+    }
 
+    /**
+     * Grows up to `desired` synapses to random cells of the sorted `candidates` that are not
+     * on the segment yet. Returns the range of the new synapses.
+     */
+    pub fn grow_synapses_from<R: Rng>(&mut self, candidates: &[Cell], initial_permanence: f32, desired: u32, rand: &mut R) -> std::ops::Range<usize> {
+        let mut cells = candidates.to_vec();
         for syn in &self.synapses {
             match cells.binary_search(&syn.cell) {
                 Ok(i) => { cells.remove(i); },
@@ -256,40 +520,66 @@ impl Segment {
         len - n_actual..len
     }
 
+    /** Number of synapses on the segment, regardless of permanence. */
+    pub fn num_potential_synapses(&self) -> usize {
+        self.synapses.len()
+    }
+
+    /** Number of synapses with a permanence of at least `connected`. */
+    pub fn num_connected_synapses(&self, connected: f32) -> usize {
+        self.synapses.iter().filter(|syn| syn.permanence >= connected).count()
+    }
+
     pub fn create_synapse(&mut self, cell: Cell, permanence: f32)
     {
         self.synapses.push(Synapse{cell: cell, permanence: permanence});
     }
 
-    fn adapt_segment(&mut self, active_cells: &FnvHashSet<Cell>, map: &mut SynapseMap, seg_ref: &SegmentRef, perm_inc: f32, perm_dec: f32, connected: f32) {
+    fn adapt_segment(&mut self, active_cells: &FnvHashSet<Cell>, map: &mut SynapseMap, seg_ref: &SegmentRef, perm_inc: f32, perm_dec: f32, connected: f32, observer: &mut Option<Box<dyn LearningObserver + Send>>) {
+        self.adapt(|cell| active_cells.contains(cell), perm_inc, perm_dec, |synapse, old_perm| {
+            if old_perm < connected {
+                if synapse.permanence >= connected {
+                     map.map.get_mut(&synapse.cell).unwrap().replace(SynapseLink{segment: seg_ref.clone(), connected: true});
+                     notify(observer, |o| o.synapse_connection_changed(seg_ref, synapse.cell, true));
+                }
+            } else if synapse.permanence < connected {
+                map.map.get_mut(&synapse.cell).unwrap().replace(SynapseLink{segment: seg_ref.clone(), connected: false});
+                notify(observer, |o| o.synapse_connection_changed(seg_ref, synapse.cell, false));
+            }
+
+            if synapse.permanence < 0.00001 {
+                map.map.get_mut(&synapse.cell).unwrap().remove(&SynapseLink{segment: seg_ref.clone(), connected: false});
+                notify(observer, |o| o.synapse_destroyed(seg_ref, synapse.cell));
+            }
+        });
+    }
+
+    /**
+     * Increments the permanence of the synapses to cells where `is_active` holds and
+     * decrements the others, clamped at 1.0. Synapses whose permanence drops to zero are
+     * removed. `changed` sees every adapted synapse with its previous permanence before
+     * it is removed.
+     */
+    pub fn adapt<F, G>(&mut self, is_active: F, perm_inc: f32, perm_dec: f32, mut changed: G)
+        where F: Fn(&Cell) -> bool, G: FnMut(&Synapse, f32)
+    {
         let mut index = 0;
         while index < self.synapses.len() {
-            let mut deleted = false;
-            
-            {
+            let deleted = {
                 let synapse = &mut self.synapses[index];
                 let old_perm = synapse.permanence;
-                if active_cells.contains(&synapse.cell) {
+                if is_active(&synapse.cell) {
                     synapse.permanence += perm_inc;
                 } else {
                     synapse.permanence -= perm_dec;
                 }
-
-                if old_perm < connected {
-                    if synapse.permanence >= connected {
-                         map.map.get_mut(&synapse.cell).unwrap().replace(SynapseLink{segment: seg_ref.clone(), connected: true});
-                    }
-                } else if synapse.permanence < connected {
-                    map.map.get_mut(&synapse.cell).unwrap().replace(SynapseLink{segment: seg_ref.clone(), connected: false});
-                }
-
                 if synapse.permanence > 1.0 {
                     synapse.permanence = 1.0;
-                } else if (synapse.permanence < 0.00001) {
-                    map.map.get_mut(&synapse.cell).unwrap().remove(&SynapseLink{segment: seg_ref.clone(), connected: false});
-                    deleted = true;
                 }
-            }
+
+                changed(synapse, old_perm);
+                synapse.permanence < 0.00001
+            };
 
             if deleted {
                 self.synapses.swap_remove(index);
@@ -303,12 +593,9 @@ impl Segment {
 impl TemporalMemory {
     pub fn new(columns: u32, cells: u32) -> TemporalMemory {
         TemporalMemory {
+            columns: columns,
             cells: cells,
-            active_cells: FnvHashSet::default(),
-            prev_active_cells: FnvHashSet::default(),
-
-            winner_cells: FnvHashSet::default(),
-            prev_winner_cells: FnvHashSet::default(),
+            state: TMState::new(),
 
             predictive_cells: FnvHashMap::default(),
 
@@ -317,15 +604,18 @@ impl TemporalMemory {
             synapses: SynapseMap::new(),
 
             iteration: 1,
+            parallel_dendrites: false,
+            observer: None,
+            forgetting: None,
+            pruned: PruneReport::default(),
 
-            segments_active: std::cell::Cell::new(Vec::new()),
-            segments_matching: std::cell::Cell::new(Vec::new()),
             empty_segments: Vec::new(),
 
             activation_threshold: 13,
             min_threshold: 10,
             max_new_synapse_count: 20,
             max_segments_per_cell: 255,
+            segment_eviction: EvictionPolicy::LeastRecentlyUsed,
             max_synapses_per_segment: 255,
             initial_permanence: 0.21,
             connected_permanence: 0.5,
@@ -335,12 +625,125 @@ impl TemporalMemory {
             predicted_segment_decrement: 0.0,
 
             rand: UniversalRng::from_seed([42, 0, 0, 0]),
+            seed: 42,
         }
     }
 
+    /** Same as `new` but a memory without columns or cells is an error. */
+    pub fn try_new(columns: u32, cells: u32) -> Result<TemporalMemory, TMError> {
+        if columns == 0 || cells == 0 {
+            return Err(TMError::ZeroSize { columns, cells });
+        }
+        Ok(TemporalMemory::new(columns, cells))
+    }
+
+    /**
+     * Feeds the next set of active columns, `active_columns` must be sorted, unique and
+     * smaller than `columns`. Panics otherwise, see `try_compute`.
+     */
     pub fn compute(&mut self, active_columns: &[usize], learn: bool) {
+        if let Err(err) = self.try_compute(active_columns, learn) {
+            panic!("{}", err);
+        }
+    }
+
+    /**
+     * Same as `compute` but invalid active columns are reported as an error
+     * and leave the memory untouched.
+     */
+    pub fn try_compute(&mut self, active_columns: &[usize], learn: bool) -> Result<(), TMError> {
+        self.try_compute_external(active_columns, &FnvHashSet::default(), learn)
+    }
+
+    /**
+     * Same as `compute` with extra presynaptic cells that are not part of the memory,
+     * such as the output of a `GridLocationLayer`. Distal segments learn on them like on
+     * previously active cells and they take part in the activation of the segments.
+     * Their columns must be at least `columns` so they can not be mistaken for cells
+     * of the memory.
+     */
+    pub fn compute_external(&mut self, active_columns: &[usize], external: &FnvHashSet<Cell>, learn: bool) {
+        if let Err(err) = self.try_compute_external(active_columns, external, learn) {
+            panic!("{}", err);
+        }
+    }
+
+    pub fn try_compute_external(&mut self, active_columns: &[usize], external: &FnvHashSet<Cell>, learn: bool) -> Result<(), TMError> {
+        self.validate_columns(active_columns)?;
+        if let Some(cell) = external.iter().find(|cell| (cell.column as usize) < self.num_columns()) {
+            return Err(TMError::ExternalColumnInRange { column: cell.column as usize, columns: self.num_columns() });
+        }
         self.active_cells(active_columns, learn);
+        self.state.external_cells.clone_from(external);
+        if learn {
+            let due = match self.forgetting {
+                Some(ref options) => options.period > 0 && self.iteration % options.period == 0,
+                None => false,
+            };
+            if due {
+                self.forget();
+            }
+        }
         self.activate_dendrites(true);
+        Ok(())
+    }
+
+    /**
+     * Runs `compute` on the activity of another stream: `state` is swapped in,
+     * computed and swapped back out, the learned connections are shared.
+     * With `learn=true` every other saved state becomes stale, see `TMState`.
+     */
+    pub fn compute_with_state(&mut self, state: &mut TMState, active_columns: &[usize], learn: bool) {
+        if let Err(err) = self.try_compute_with_state(state, active_columns, learn) {
+            panic!("{}", err);
+        }
+    }
+
+    pub fn try_compute_with_state(&mut self, state: &mut TMState, active_columns: &[usize], learn: bool) -> Result<(), TMError> {
+        self.swap_state(state);
+        let result = self.try_compute(active_columns, learn);
+        self.swap_state(state);
+        result
+    }
+
+    /**
+     * Exchanges the current activity with `state`. The segments `state` refers to are not
+     * checked, it must not have been set aside while the memory learned, see `TMState`.
+     */
+    pub fn swap_state(&mut self, state: &mut TMState) {
+        std::mem::swap(&mut self.state, state);
+        self.predictive_cells.clear();
+    }
+
+    pub fn validate_columns(&self, active_columns: &[usize]) -> Result<(), TMError> {
+        let mut previous: Option<usize> = None;
+        for &column in active_columns {
+            if column >= self.num_columns() {
+                return Err(TMError::ColumnOutOfRange { column, columns: self.num_columns() });
+            }
+            if let Some(previous) = previous {
+                if previous == column {
+                    return Err(TMError::DuplicateColumn(column));
+                } else if previous > column {
+                    return Err(TMError::UnsortedColumns { previous, column });
+                }
+            }
+            previous = Some(column);
+        }
+        Ok(())
+    }
+
+    pub fn num_columns(&self) -> usize {
+        self.columns as usize
+    }
+
+    pub fn cells_per_column(&self) -> usize {
+        self.cells as usize
+    }
+
+    /** Total number of cells, the input size of anything reading the active cells. */
+    pub fn num_cells(&self) -> usize {
+        self.num_columns() * self.cells_per_column()
     }
 
     #[inline]
@@ -373,35 +776,25 @@ impl TemporalMemory {
    
 
     pub fn reset(&mut self) {
-        self.winner_cells.clear();
-        self.active_cells.clear();
-        self.segments_matching.get_mut().clear();
-        self.segments_active.get_mut().clear();
-        self.active_cells.clear();
+        self.state.reset();
         self.predictive_cells.clear();
     }
 
     pub fn activate_dendrites(&mut self, learn: bool) {
-        let mut segments_active = self.segments_active.get_mut();
-        let mut segments_matching = self.segments_matching.get_mut();
+        let segments_active = &mut self.state.segments_active;
+        let segments_matching = &mut self.state.segments_matching;
 
         segments_active.clear();
         segments_matching.clear();
         self.segments_am_helper.clear();
         self.predictive_cells.clear();
 
-        for cell in self.active_cells.iter() {
-            match self.synapses.map.get(&cell) {
-                Some(vec) => {
-                    for syn_link in vec {
-                       let mut val = &mut self.segments_am_helper.entry(syn_link.segment.clone()).or_insert((0, 0));
-                       val.0 += (syn_link.connected as u32);
-                       val.1 += 1;
-                    }   
-                },
-                None => {},
-            }
+        if self.parallel_dendrites {
+            self.synapses.compute_activity_parallel(&self.state.active_cells, &mut self.segments_am_helper);
+        } else {
+            self.synapses.compute_activity(&self.state.active_cells, &mut self.segments_am_helper);
         }
+        self.synapses.compute_activity(&self.state.external_cells, &mut self.segments_am_helper);
 
         for (key, val) in self.segments_am_helper.iter() {
             if val.0 >= self.activation_threshold {
@@ -419,10 +812,10 @@ impl TemporalMemory {
             self.iteration += 1;
         }
         
-        quickersort::sort(&mut segments_active);
-        quickersort::sort(&mut segments_matching);
-        debug!("active_cells {:?}", self.active_cells);
-        debug!("winner_cells {:?}", self.winner_cells);
+        quickersort::sort(segments_active);
+        quickersort::sort(segments_matching);
+        debug!("active_cells {:?}", self.state.active_cells);
+        debug!("winner_cells {:?}", self.state.winner_cells);
         debug!("active {:?}", segments_active);
         debug!("matching {:?}", segments_matching);
     }
@@ -430,18 +823,23 @@ impl TemporalMemory {
     
     pub fn active_cells(&mut self,active_columns: &[usize], learn: bool)
     {
-        let segments_active = self.segments_active.take();
-        let segments_matching = self.segments_matching.take();
+        let segments_active = std::mem::take(&mut self.state.segments_active);
+        let segments_matching = std::mem::take(&mut self.state.segments_matching);
         {
         let mut iter = active_columns.iter().peekable();
         let mut iter_active_segs = segments_active.iter().peekable();
         let mut iter_matching_segs = segments_matching.iter().peekable();
 
         use std::mem;
-        mem::swap(&mut self.prev_active_cells, &mut self.active_cells);
-        mem::swap(&mut self.prev_winner_cells, &mut self.winner_cells);
-        self.active_cells.clear();
-        self.winner_cells.clear();
+        mem::swap(&mut self.state.prev_active_cells, &mut self.state.active_cells);
+        mem::swap(&mut self.state.prev_winner_cells, &mut self.state.winner_cells);
+        self.state.active_cells.clear();
+        self.state.winner_cells.clear();
+        self.state.predicted_active_cells.clear();
+        for cell in self.state.external_cells.drain() {
+            self.state.prev_active_cells.insert(cell);
+            self.state.prev_winner_cells.insert(cell);
+        }
 
         loop {
             let curr_column = match iter.peek() {
@@ -488,7 +886,7 @@ impl TemporalMemory {
                 } else {
                      //Burst whole Column
                      for cell in 0..self.cells {
-                         self.active_cells.insert(Cell{column:column_idx, cell: cell});
+                         self.state.active_cells.insert(Cell{column:column_idx, cell: cell});
                      }
                      if matching_segment {  
                         self.adapt_best_segment(&mut matching_segs, learn);
@@ -513,34 +911,34 @@ impl TemporalMemory {
         }
         }
 
-        self.segments_active.replace(segments_active);
-        self.segments_matching.replace(segments_matching);
+        self.state.segments_active = segments_active;
+        self.state.segments_matching = segments_matching;
     }   
 
     
 
     fn grow_segment(&mut self, column: u32) {
         let cell = self.segments.least_used_cell(column, &mut self.rand, self.cells);
-        self.winner_cells.insert(cell);
-        let n_grow_desired = cmp::min(self.max_new_synapse_count, self.prev_winner_cells.len() as u32);
+        self.state.winner_cells.insert(cell);
+        let n_grow_desired = cmp::min(self.max_new_synapse_count, self.state.prev_winner_cells.len() as u32);
         if n_grow_desired > 0 {
             //pub fn grow_synapses<R: Rng>(&mut self, segment: &mut Segment, segment_ref: &SegmentRef, active_cells: &FnvHashSet<Cell>, rand: &mut R, initial_permanence: f32, connected: f32, desired: u32, max_synapses: u32) 
-            let (seg, seg_ref) = self.segments.insert(&mut self.synapses, Segment::new(cell.column, cell.cell, self.iteration), self.max_segments_per_cell);
-            self.synapses.grow_synapses(seg, &seg_ref, &self.prev_winner_cells, &mut self.rand, self.initial_permanence, self.connected_permanence, n_grow_desired  as u32, self.max_synapses_per_segment);
+            let (seg, seg_ref) = self.segments.insert(&mut self.synapses, Segment::new(cell.column, cell.cell, self.iteration), self.max_segments_per_cell, &self.segment_eviction, self.connected_permanence, &mut self.rand, &mut self.observer);
+            self.synapses.grow_synapses(seg, &seg_ref, &self.state.prev_winner_cells, &mut self.rand, self.initial_permanence, self.connected_permanence, n_grow_desired  as u32, self.max_synapses_per_segment, &mut self.observer);
             debug!("New Segment {:?}", seg);
         }
     }
 
     fn adapt_best_segment<'a, I: Iterator<Item=&'a SegmentScore>>(&mut self, matching_segs: &mut I, learn: bool) {
         let best_seg = matching_segs.max_by_key(|s| s.matched).unwrap();
-        self.winner_cells.insert(best_seg.segment.cell);
+        self.state.winner_cells.insert(best_seg.segment.cell);
         let seg = self.segments.get_segment_mut(&best_seg.segment);
         if learn {
-            //might be better to use  &self.prev_winner_cells somehow without touching the not relevant ones
-            seg.adapt_segment(&self.prev_active_cells,  &mut self.synapses, &best_seg.segment, self.permanence_increment, self.permanence_decrement, self.connected_permanence);
+            //might be better to use  &self.state.prev_winner_cells somehow without touching the not relevant ones
+            seg.adapt_segment(&self.state.prev_active_cells,  &mut self.synapses, &best_seg.segment, self.permanence_increment, self.permanence_decrement, self.connected_permanence, &mut self.observer);
             let n_grow_desired = self.max_new_synapse_count  as i32 - best_seg.matched as i32;
             if n_grow_desired > 0 {
-                self.synapses.grow_synapses(seg, &best_seg.segment, &self.prev_winner_cells, &mut self.rand, self.initial_permanence, self.connected_permanence, n_grow_desired as u32, self.max_synapses_per_segment);
+                self.synapses.grow_synapses(seg, &best_seg.segment, &self.state.prev_winner_cells, &mut self.rand, self.initial_permanence, self.connected_permanence, n_grow_desired as u32, self.max_synapses_per_segment, &mut self.observer);
             }
             debug!("Update Matching {:?}", seg);
         }
@@ -549,7 +947,7 @@ impl TemporalMemory {
     fn punish_predicted_columns<'a, I: Iterator<Item=&'a SegmentScore>>(&mut self, matching_segs: &mut I) {
          for seg_ref in matching_segs {
             let seg = self.segments.get_segment_mut(&seg_ref.segment);
-            seg.adapt_segment(&self.prev_active_cells, &mut self.synapses, &seg_ref.segment, -self.predicted_segment_decrement, 0.0, self.connected_permanence);
+            seg.adapt_segment(&self.state.prev_active_cells, &mut self.synapses, &seg_ref.segment, -self.predicted_segment_decrement, 0.0, self.connected_permanence, &mut self.observer);
             debug!("Punish {:?}",  seg);
             //debug!("AfterPunish {:?}", seg.synapses);
         }
@@ -558,18 +956,60 @@ impl TemporalMemory {
     fn activate_predicted_column<'a, I: Iterator<Item=&'a SegmentScore>>(&mut self, active_segs: &mut I, learn: bool) {
         for active_seg in active_segs {
             debug!("Reward {:?}", active_seg.segment);
-            self.active_cells.insert(active_seg.segment.cell);
-            self.winner_cells.insert(active_seg.segment.cell);
+            self.state.active_cells.insert(active_seg.segment.cell);
+            self.state.winner_cells.insert(active_seg.segment.cell);
+            self.state.predicted_active_cells.insert(active_seg.segment.cell);
             if learn {
                 let seg = self.segments.get_segment_mut(&active_seg.segment);
                 let active_potential = active_seg.matched;    
-                seg.adapt_segment(&self.prev_active_cells, &mut self.synapses, &active_seg.segment, self.permanence_increment, self.permanence_decrement, self.connected_permanence);
+                seg.adapt_segment(&self.state.prev_active_cells, &mut self.synapses, &active_seg.segment, self.permanence_increment, self.permanence_decrement, self.connected_permanence, &mut self.observer);
                 let n_grow_desired = self.max_new_synapse_count  as i32 - active_potential as i32;
                 if n_grow_desired > 0 {
-                    self.synapses.grow_synapses(seg, &active_seg.segment, &self.prev_winner_cells, &mut self.rand, self.initial_permanence, self.connected_permanence, n_grow_desired  as u32, self.max_synapses_per_segment);
+                    self.synapses.grow_synapses(seg, &active_seg.segment, &self.state.prev_winner_cells, &mut self.rand, self.initial_permanence, self.connected_permanence, n_grow_desired  as u32, self.max_synapses_per_segment, &mut self.observer);
+                }
+            }
+        }
+    }
+
+    /**
+     * Decays the synapses of every segment that has not been active for
+     * `forgetting.unused_iterations` iterations and destroys the segments that are
+     * left with less than `min_threshold` synapses, their slots are reused by new segments.
+     * Runs automatically every `forgetting.period` learning iterations, does nothing
+     * when `forgetting` is `None`.
+     */
+    pub fn forget(&mut self) -> PruneReport {
+        let mut report = PruneReport::default();
+        let options = match self.forgetting {
+            Some(ref options) => options.clone(),
+            None => return report,
+        };
+        let no_cells = FnvHashSet::default();
+
+        for (cell, segments) in self.segments.map.iter_mut() {
+            for (index, seg) in segments.iter_mut().enumerate() {
+                if seg.synapses.is_empty() || self.iteration.saturating_sub(seg.last_used) < options.unused_iterations {
+                    continue;
+                }
+                let seg_ref = SegmentRef{ cell: *cell, segment: index as u32 };
+                let before = seg.synapses.len();
+                seg.adapt_segment(&no_cells, &mut self.synapses, &seg_ref, 0.0, options.permanence_decay, self.connected_permanence, &mut self.observer);
+                report.segments_decayed += 1;
+                report.synapses_removed += before - seg.synapses.len();
+
+                if (seg.synapses.len() as u32) < self.min_threshold {
+                    self.synapses.remove_links(seg, &seg_ref, &mut self.observer);
+                    notify(&mut self.observer, |o| o.segment_destroyed(&seg_ref));
+                    report.synapses_removed += seg.synapses.len();
+                    report.segments_removed += 1;
+                    seg.synapses.clear();
                 }
             }
         }
+
+        debug!("Forget {:?}", report);
+        self.pruned.add(&report);
+        report
     }
 
     pub fn get_cell(&self, cell: usize) -> Cell {
@@ -581,9 +1021,9 @@ impl TemporalMemory {
     }
 
     pub fn add_segment(&mut self, mut segment: Segment) {
-        let (seg, seg_ref) = self.segments.insert(&mut self.synapses, segment, self.max_segments_per_cell);
+        let (seg, seg_ref) = self.segments.insert(&mut self.synapses, segment, self.max_segments_per_cell, &self.segment_eviction, self.connected_permanence, &mut self.rand, &mut self.observer);
         let range =  0..seg.synapses.len();
-        self.synapses.add_synapses(seg, range, &seg_ref, self.connected_permanence, self.max_synapses_per_segment);
+        self.synapses.add_synapses(seg, range, &seg_ref, self.connected_permanence, self.max_synapses_per_segment, &mut self.observer);
     }   
 
 
@@ -598,16 +1038,270 @@ impl TemporalMemory {
         }
     }
 
+    /** All cells that own at least one segment. */
+    pub fn cells_with_segments<'a>(&'a self) -> impl Iterator<Item=Cell> + 'a {
+        self.segments.map.iter().filter(|&(_, segs)| !segs.is_empty()).map(|(cell, _)| *cell)
+    }
+
+    /** All segments together with their references, including recycled empty ones. */
+    pub fn segments<'a>(&'a self) -> impl Iterator<Item=(SegmentRef, &'a Segment)> + 'a {
+        self.segments.map.iter().flat_map(|(cell, segs)| {
+            let cell = *cell;
+            segs.iter().enumerate().map(move |(index, seg)| (SegmentRef{ cell, segment: index as u32 }, seg))
+        })
+    }
+
+    pub fn get_segment(&self, seg_ref: &SegmentRef) -> &Segment {
+        self.segments.get_segment(seg_ref)
+    }
+
+    /** Number of iterations since the segment was last active. */
+    pub fn segment_age(&self, segment: &Segment) -> u64 {
+        self.iteration.saturating_sub(segment.last_used)
+    }
+
+    /** The links of the reverse index, one per segment that has a synapse from `presynaptic`. */
+    pub fn presynaptic_links<'a>(&'a self, presynaptic: Cell) -> impl Iterator<Item=&'a SynapseLink> + 'a {
+        self.synapses.map.get(&presynaptic).into_iter().flat_map(|links| links.iter())
+    }
+
+    /** Every synapse whose presynaptic cell is `presynaptic`, with the segment it belongs to. */
+    pub fn synapses_for_presynaptic_cell<'a>(&'a self, presynaptic: Cell) -> impl Iterator<Item=(&'a SegmentRef, &'a Synapse)> + 'a {
+        self.presynaptic_links(presynaptic).filter_map(move |link| {
+            self.segments.get_segment(&link.segment)
+                .synapses
+                .iter()
+                .find(|syn| syn.cell == presynaptic)
+                .map(|syn| (&link.segment, syn))
+        })
+    }
+
+    /**
+     * Per column statistics of the learned connections, sorted by column.
+     * Only columns that have at least one segment are reported, the empty slots left by
+     * forgetting and eviction are not counted as segments.
+     */
+    pub fn column_statistics(&self) -> impl Iterator<Item=ColumnStatistics> {
+        let mut columns: FnvHashMap<u32, ColumnStatistics> = FnvHashMap::default();
+        for (cell, segs) in self.segments.map.iter() {
+            if segs.iter().all(|seg| seg.synapses.is_empty()) {
+                continue;
+            }
+            let stats = columns.entry(cell.column).or_insert_with(|| ColumnStatistics::new(cell.column));
+            stats.cells_with_segments += 1;
+            for seg in segs.iter().filter(|seg| !seg.synapses.is_empty()) {
+                stats.segments += 1;
+                stats.potential_synapses += seg.num_potential_synapses();
+                stats.connected_synapses += seg.num_connected_synapses(self.connected_permanence);
+                stats.oldest_segment_age = cmp::max(stats.oldest_segment_age, self.segment_age(seg));
+            }
+        }
+        let mut stats: Vec<ColumnStatistics> = columns.into_values().collect();
+        stats.sort_by_key(|s| s.column);
+        stats.into_iter()
+    }
+
+    /** Cells active in the current step of the current state. */
+    pub fn get_active_cells(&self) -> &FnvHashSet<Cell> {
+        &self.state.active_cells
+    }
+
+    pub fn get_prev_active_cells(&self) -> &FnvHashSet<Cell> {
+        &self.state.prev_active_cells
+    }
+
+    pub fn get_winner_cells(&self) -> &FnvHashSet<Cell> {
+        &self.state.winner_cells
+    }
+
+    pub fn get_prev_winner_cells(&self) -> &FnvHashSet<Cell> {
+        &self.state.prev_winner_cells
+    }
+
+    pub fn get_predicted_active_cells(&self) -> &FnvHashSet<Cell> {
+        &self.state.predicted_active_cells
+    }
+
+    pub fn get_active_segments(&self) -> &[SegmentScore] {
+        &self.state.segments_active
+    }
+
+    pub fn get_matching_segments(&self) -> &[SegmentScore] {
+        &self.state.segments_matching
+    }
+
     pub fn get_predictive_cells(&mut self) -> &FnvHashMap<Cell, u32> {
         self.predictive_cells.clear();
-        for seg in self.segments_active.get_mut() {
+        for seg in &self.state.segments_active {
             *self.predictive_cells.entry(seg.segment.cell).or_insert(0) += 1;
         }
         &self.predictive_cells
     }
+
+    /**
+     * Ranks the columns predicted for the next step. The score of a column is the sum of
+     * the active synapse counts (`SegmentScore::matched`) of its active segments, plus
+     * `matching_weight` times the counts of its segments that are only matching.
+     * Scores are normalized to sum to 1 and sorted from the most confident column.
+     */
+    pub fn predicted_columns(&self, matching_weight: f32) -> Vec<ColumnPrediction> {
+        let mut scores: FnvHashMap<u32, f32> = FnvHashMap::default();
+        for seg in &self.state.segments_active {
+            *scores.entry(seg.segment.cell.column).or_insert(0.0) += seg.matched as f32;
+        }
+        if matching_weight > 0.0 {
+            for seg in &self.state.segments_matching {
+                if self.state.segments_active.binary_search(seg).is_err() {
+                    *scores.entry(seg.segment.cell.column).or_insert(0.0) += matching_weight * seg.matched as f32;
+                }
+            }
+        }
+
+        let total: f32 = scores.values().sum();
+        let mut predictions: Vec<ColumnPrediction> = scores.into_iter()
+            .filter(|&(_, score)| score > 0.0)
+            .map(|(column, score)| ColumnPrediction { column: column as usize, confidence: score / total })
+            .collect();
+        predictions.sort_by(|a, b| b.confidence.partial_cmp(&a.confidence).unwrap().then(a.column.cmp(&b.column)));
+        predictions
+    }
+
+    /** The `k` most confident entries of `predicted_columns`. */
+    pub fn top_predicted_columns(&self, k: usize, matching_weight: f32) -> Vec<ColumnPrediction> {
+        let mut predictions = self.predicted_columns(matching_weight);
+        predictions.truncate(k);
+        predictions
+    }
+
+    /**
+     * Returns the cells that would be predictive if `active_cells` were the
+     * current active cells, with the number of active segments on each of them.
+     * Nothing is learned and `iteration` is left untouched.
+     */
+    pub fn predict_cells(&self, active_cells: &FnvHashSet<Cell>) -> FnvHashMap<Cell, u32> {
+        let mut activity = FnvHashMap::default();
+        self.synapses.compute_activity(active_cells, &mut activity);
+
+        let mut predicted = FnvHashMap::default();
+        for (seg, val) in activity.iter() {
+            if val.0 >= self.activation_threshold {
+                *predicted.entry(seg.cell).or_insert(0) += 1;
+            }
+        }
+        predicted
+    }
+
+    /**
+     * Generates the sequence the memory expects after `active_cells`, without feeding
+     * real inputs and without learning.
+     *
+     * On every step the predicted cells of a branch are used as the active cells of
+     * the next step. Predicted cells that are not connected through common active
+     * presynaptic cells are split into separate branches (e.g. a bursting column
+     * predicting two different contexts). At most `max_branches` branches are kept
+     * per step, the ones with the most predicted cells win.
+     *
+     * The result has one entry per generated step, it stops early once nothing is
+     * predicted anymore.
+     */
+    pub fn rollout(&self, active_cells: &FnvHashSet<Cell>, steps: usize, max_branches: usize) -> Vec<Vec<PredictionBranch>> {
+        let max_branches = cmp::max(max_branches, 1);
+        let mut result: Vec<Vec<PredictionBranch>> = Vec::with_capacity(steps);
+        let mut frontier = vec![(None, active_cells.clone())];
+
+        for _ in 0..steps {
+            let mut branches = Vec::new();
+            for &(parent, ref cells) in &frontier {
+                for predicted in self.split_predictions(cells) {
+                    let mut columns: Vec<usize> = predicted.iter().map(|c| c.column as usize).collect();
+                    columns.dedup();
+                    branches.push(PredictionBranch {
+                        parent,
+                        columns,
+                        cells: predicted,
+                    });
+                }
+            }
+
+            if branches.is_empty() {
+                break;
+            }
+
+            if branches.len() > max_branches {
+                branches.sort_by_key(|b| cmp::Reverse(b.cells.len()));
+                branches.truncate(max_branches);
+                branches.sort_by(|a, b| (a.parent, &a.cells).cmp(&(b.parent, &b.cells)));
+            }
+
+            let step_index = result.len();
+            frontier = branches.iter()
+                .enumerate()
+                .map(|(index, branch)| (Some(index), branch.cells.iter().cloned().collect()))
+                .collect();
+            debug!("rollout step {} {:?}", step_index, branches);
+            result.push(branches);
+        }
+        result
+    }
+
+    /**
+     * Predicts from `active_cells` and groups the predicted cells into independent
+     * branches: two predicted cells belong to the same branch when their active
+     * segments share an active presynaptic cell.
+     */
+    fn split_predictions(&self, active_cells: &FnvHashSet<Cell>) -> Vec<Vec<Cell>> {
+        let mut activity = FnvHashMap::default();
+        self.synapses.compute_activity(active_cells, &mut activity);
+
+        let mut active_segs: Vec<&SegmentRef> = activity.iter()
+            .filter(|&(_, val)| val.0 >= self.activation_threshold)
+            .map(|(seg, _)| seg)
+            .collect();
+        active_segs.sort();
+
+        let mut cells: Vec<Cell> = Vec::new();
+        let mut cell_index: FnvHashMap<Cell, usize> = FnvHashMap::default();
+        let mut parents: Vec<usize> = Vec::new();
+        let mut owners: FnvHashMap<Cell, usize> = FnvHashMap::default();
+
+        for seg_ref in active_segs {
+            let index = *cell_index.entry(seg_ref.cell).or_insert_with(|| {
+                cells.push(seg_ref.cell);
+                parents.push(parents.len());
+                parents.len() - 1
+            });
+            for syn in &self.segments.get_segment(seg_ref).synapses {
+                if syn.permanence >= self.connected_permanence && active_cells.contains(&syn.cell) {
+                    let owner = *owners.entry(syn.cell).or_insert(index);
+                    let (a, b) = (find_root(&mut parents, owner), find_root(&mut parents, index));
+                    if a != b {
+                        parents[cmp::max(a, b)] = cmp::min(a, b);
+                    }
+                }
+            }
+        }
+
+        let mut groups: FnvHashMap<usize, Vec<Cell>> = FnvHashMap::default();
+        for (index, &cell) in cells.iter().enumerate() {
+            let root = find_root(&mut parents, index);
+            groups.entry(root).or_default().push(cell);
+        }
+
+        let mut branches: Vec<Vec<Cell>> = groups.into_values().map(|mut group| { group.sort(); group }).collect();
+        branches.sort();
+        branches
+    }
+}
+
+fn find_root(parents: &mut [usize], mut index: usize) -> usize {
+    while parents[index] != index {
+        parents[index] = parents[parents[index]];
+        index = parents[index];
+    }
+    index
 }
 
-#[derive(Copy,Clone,PartialEq,Eq,Hash)]
+#[derive(Copy,Clone,PartialEq,Eq,Hash,Serialize,Deserialize)]
 pub struct Cell {
     pub column: u32,
     pub cell: u32,
@@ -621,7 +1315,6 @@ impl Cell {
     }
 }
 
-use std::fmt;
 impl fmt::Display for Cell {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match f.precision() {
@@ -660,7 +1353,7 @@ impl PartialOrd for Cell {
 
 
 
-#[derive(Debug,Clone,Eq,Hash,PartialEq)]
+#[derive(Debug,Clone,Eq,Hash,PartialEq,Serialize,Deserialize)]
 pub struct SegmentRef {
     pub cell: Cell,
     pub segment: u32,
@@ -683,6 +1376,7 @@ impl PartialOrd for SegmentRef {
     }
 }
 
+#[derive(Clone,Serialize,Deserialize)]
 pub struct SegmentScore {
     pub segment: SegmentRef,
     pub matched: u32,
@@ -722,6 +1416,56 @@ impl std::hash::Hash for SegmentScore {
     }
 }
 
+/**
+ * Confidence that a column becomes active on the next step, see `TemporalMemory::predicted_columns`.
+ */
+#[derive(Debug,Clone,PartialEq)]
+pub struct ColumnPrediction {
+    pub column: usize,
+    pub confidence: f32,
+}
+
+/**
+ * One alternative of a multi-step prediction made by `TemporalMemory::rollout`.
+ */
+#[derive(Debug,Clone)]
+pub struct PredictionBranch {
+    /** Index of the branch in the previous step this one continues, `None` on the first step. */
+    pub parent: Option<usize>,
+    /** Sorted predicted columns. */
+    pub columns: Vec<usize>,
+    /** Sorted predicted cells, used as the active cells of the next step. */
+    pub cells: Vec<Cell>,
+}
+
+/**
+ * Summary of the segments grown by the cells of one column,
+ * see `TemporalMemory::column_statistics`.
+ */
+#[derive(Debug,Clone,PartialEq)]
+pub struct ColumnStatistics {
+    pub column: u32,
+    pub cells_with_segments: usize,
+    pub segments: usize,
+    pub potential_synapses: usize,
+    pub connected_synapses: usize,
+    /** Largest number of iterations since any segment of the column was active. */
+    pub oldest_segment_age: u64,
+}
+
+impl ColumnStatistics {
+    fn new(column: u32) -> ColumnStatistics {
+        ColumnStatistics {
+            column,
+            cells_with_segments: 0,
+            segments: 0,
+            potential_synapses: 0,
+            connected_synapses: 0,
+            oldest_segment_age: 0,
+        }
+    }
+}
+
 #[derive(Debug,Eq,PartialEq,Clone,Copy)]
 enum SynapseStatus {
     Alive = 1,
@@ -734,6 +1478,12 @@ pub struct SynapseLink {
     connected: bool,
 }
 
+impl SynapseLink {
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+}
+
 impl Ord for SynapseLink {
     fn cmp(&self, other: &Self) -> Ordering {
         self.segment.cmp(&other.segment)
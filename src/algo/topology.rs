@@ -211,6 +211,9 @@ impl Topology {
         let mut dimension_multiples = vec![0usize;dimensions.len()];
         let mut holder = 1;
         let len = dimensions.len();
+        if len == 0 {
+            return dimension_multiples;
+        }
         dimension_multiples[len - 1] = 1;
         for i in 1..len {
             holder *= dimensions[len - i] as usize;
@@ -0,0 +1,266 @@
+use std;
+use std::cmp;
+use std::fmt;
+use algo::{PotentialPool, SpatialPooler, SynapsePermenenceOptions, TemporalMemory};
+use util::universal_rand::*;
+use quickersort;
+
+#[derive(Debug,Clone,PartialEq)]
+pub enum UPError {
+    InputOutOfRange { index: usize, size: usize },
+    /** `compute` was called before `init`. */
+    NotInitialized,
+}
+
+impl fmt::Display for UPError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            UPError::InputOutOfRange { index, size } => write!(f, "input cell {} is out of range, the union pooler has {} inputs", index, size),
+            UPError::NotInitialized => write!(f, "the union pooler must be initialized before compute"),
+        }
+    }
+}
+
+impl std::error::Error for UPError {
+    fn description(&self) -> &str {
+        match *self {
+            UPError::InputOutOfRange { .. } => "input cell out of range",
+            UPError::NotInitialized => "union pooler not initialized",
+        }
+    }
+}
+
+/**
+ * Temporal pooling layer that sits on top of a `TemporalMemory` (the union pooler of htmresearch).
+ * Columns are driven by the active and predicted-active cells of the TM through proximal
+ * synapses like the `SpatialPooler`, predicted-active input being weighted more heavily.
+ * Every winning column accumulates a pooling activation that decays each step, and the
+ * union of the most active columns forms an output SDR that stays stable while a learned
+ * sequence is being predicted.
+ */
+pub struct UnionPooler {
+    pub rand: UniversalRng,
+    pub potential: PotentialPool,
+    pub syn_perm_options: SynapsePermenenceOptions,
+
+    pub num_inputs: usize,
+    pub num_columns: usize,
+
+    pub potential_pct: f64,
+    pub init_connected_pct: f32,
+    pub stimulus_threshold: f32,
+    /** Columns winning the inhibition each step. */
+    pub num_active_columns: usize,
+
+    pub active_overlap_weight: f32,
+    pub predicted_active_overlap_weight: f32,
+    /** Fraction of the columns that can be part of the union. */
+    pub max_union_activity: f32,
+    /** Fraction of the pooling activation lost every step. */
+    pub pooling_decay: f32,
+
+    pub iteration_num: u32,
+    pub overlaps: Vec<f32>,
+    pub pooling_activation: Vec<f32>,
+    pub active_columns: Vec<usize>,
+    pub union_sdr: Vec<usize>,
+
+    input_scratch: Vec<bool>,
+}
+
+impl UnionPooler {
+    pub fn new(num_inputs: usize, num_columns: usize) -> UnionPooler {
+        UnionPooler {
+            rand: UniversalRng::from_seed([42, 0, 0, 0]),
+            potential: PotentialPool::new(0, 0),
+            syn_perm_options: SynapsePermenenceOptions {
+                inactive_dec: 0.01,
+                min: 0.0,
+                max: 1.0,
+                connected: 0.10,
+                below_stimulus_inc: 0.10 / 10.0,
+                active_inc: 0.1,
+                trim_threshold: 0.1 / 2.0,
+            },
+
+            num_inputs,
+            num_columns,
+
+            potential_pct: 0.5,
+            init_connected_pct: 0.5,
+            stimulus_threshold: 0.0,
+            num_active_columns: cmp::max(1, num_columns / 50),
+
+            active_overlap_weight: 1.0,
+            predicted_active_overlap_weight: 10.0,
+            max_union_activity: 0.2,
+            pooling_decay: 0.1,
+
+            iteration_num: 0,
+            overlaps: vec![0.0; num_columns],
+            pooling_activation: vec![0.0; num_columns],
+            active_columns: Vec::new(),
+            union_sdr: Vec::new(),
+
+            input_scratch: vec![false; num_inputs],
+        }
+    }
+
+    /** Samples the potential pool of every column over the whole input space. */
+    pub fn init(&mut self) {
+        self.syn_perm_options.below_stimulus_inc = self.syn_perm_options.connected / 10.0;
+        self.syn_perm_options.trim_threshold = self.syn_perm_options.active_inc / 2.0;
+        self.potential = PotentialPool::new(self.num_columns, self.num_inputs);
+
+        let amount = (self.num_inputs as f64 * self.potential_pct + 0.5) as usize;
+        let mut arr = vec![0usize; self.num_inputs];
+        for column in 0..self.num_columns {
+            let range = SpatialPooler::sample_into(&mut self.rand, 0..self.num_inputs, amount, &mut arr);
+            quickersort::sort(&mut arr[range.clone()]);
+            self.potential.setup_pool(column,
+                                      &arr[range],
+                                      self.init_connected_pct,
+                                      &self.syn_perm_options,
+                                      &mut self.rand);
+            self.potential.update_permanences(column,
+                                              true,
+                                              (self.stimulus_threshold + 0.5) as i32,
+                                              &self.syn_perm_options);
+        }
+    }
+
+    /**
+     * Pools one step of TM activity, both slices are cell indices below `num_inputs`.
+     * `predicted_active` should be a subset of `active`. Panics on other cells or before `init`,
+     * see `try_compute`.
+     */
+    pub fn compute(&mut self, active: &[usize], predicted_active: &[usize], learn: bool) {
+        if let Err(err) = self.try_compute(active, predicted_active, learn) {
+            panic!("{}", err);
+        }
+    }
+
+    /**
+     * Same as `compute` but a cell out of range or a pooler that was not initialized is
+     * reported as an error and leaves the pooler untouched.
+     */
+    pub fn try_compute(&mut self, active: &[usize], predicted_active: &[usize], learn: bool) -> Result<(), UPError> {
+        if self.potential.num_columns() != self.num_columns {
+            return Err(UPError::NotInitialized);
+        }
+        if let Some(&index) = active.iter().chain(predicted_active.iter()).find(|&&index| index >= self.num_inputs) {
+            return Err(UPError::InputOutOfRange { index, size: self.num_inputs });
+        }
+        self.iteration_num += 1;
+        self.calculate_overlaps(active, predicted_active);
+        self.inhibit_columns();
+        self.update_pooling_activation();
+        self.update_union();
+
+        if learn {
+            self.adapt_synapses(active);
+        }
+        Ok(())
+    }
+
+    /** Pools the current activity of `tm`, whose `num_cells` must equal `num_inputs`. */
+    pub fn compute_from_tm(&mut self, tm: &TemporalMemory, learn: bool) {
+        if let Err(err) = self.try_compute_from_tm(tm, learn) {
+            panic!("{}", err);
+        }
+    }
+
+    pub fn try_compute_from_tm(&mut self, tm: &TemporalMemory, learn: bool) -> Result<(), UPError> {
+        let active: Vec<usize> = tm.state.active_cells.iter().map(|cell| cell.index(tm.cells) as usize).collect();
+        let predicted_active: Vec<usize> = tm.state.predicted_active_cells.iter().map(|cell| cell.index(tm.cells) as usize).collect();
+        self.try_compute(&active, &predicted_active, learn)
+    }
+
+    /** Forgets the pooled history, call between sequences. */
+    pub fn reset(&mut self) {
+        for activation in &mut self.pooling_activation {
+            *activation = 0.0;
+        }
+        self.active_columns.clear();
+        self.union_sdr.clear();
+    }
+
+    pub fn calculate_overlaps(&mut self, active: &[usize], predicted_active: &[usize]) {
+        for overlap in &mut self.overlaps {
+            *overlap = 0.0;
+        }
+        let (active_weight, predicted_weight) = (self.active_overlap_weight, self.predicted_active_overlap_weight);
+        self.add_overlaps(active, active_weight);
+        self.add_overlaps(predicted_active, predicted_weight);
+    }
+
+    /** Adds `weight` times the connected synapses on `inputs` to the overlap of every column. */
+    fn add_overlaps(&mut self, inputs: &[usize], weight: f32) {
+        for &input in inputs {
+            self.input_scratch[input] = true;
+        }
+        for column in 0..self.num_columns {
+            let counter = self.potential.connected_by_column(column)
+                .iter()
+                .filter(|con| self.input_scratch[con.index])
+                .count();
+            self.overlaps[column] += counter as f32 * weight;
+        }
+        for &input in inputs {
+            self.input_scratch[input] = false;
+        }
+    }
+
+    pub fn inhibit_columns(&mut self) {
+        let overlaps = &self.overlaps;
+        let stimulus_threshold = self.stimulus_threshold;
+        self.active_columns.clear();
+        self.active_columns.extend((0..self.num_columns).filter(|&c| overlaps[c] > 0.0 && overlaps[c] >= stimulus_threshold));
+        self.active_columns.sort_by(|&a, &b| overlaps[b].partial_cmp(&overlaps[a]).unwrap().then(a.cmp(&b)));
+        self.active_columns.truncate(self.num_active_columns);
+        self.active_columns.sort();
+    }
+
+    pub fn update_pooling_activation(&mut self) {
+        let keep = 1.0 - self.pooling_decay;
+        for activation in &mut self.pooling_activation {
+            *activation *= keep;
+        }
+        for &column in &self.active_columns {
+            self.pooling_activation[column] += self.overlaps[column];
+        }
+    }
+
+    pub fn update_union(&mut self) {
+        let max_union = (self.max_union_activity * self.num_columns as f32) as usize;
+        let activation = &self.pooling_activation;
+        self.union_sdr.clear();
+        self.union_sdr.extend((0..self.num_columns).filter(|&c| activation[c] > 0.0));
+        self.union_sdr.sort_by(|&a, &b| activation[b].partial_cmp(&activation[a]).unwrap().then(a.cmp(&b)));
+        self.union_sdr.truncate(max_union);
+        self.union_sdr.sort();
+    }
+
+    /** Hebbian learning of the winning columns towards the active cells. */
+    pub fn adapt_synapses(&mut self, active: &[usize]) {
+        for &input in active {
+            self.input_scratch[input] = true;
+        }
+        for &column in &self.active_columns {
+            for syn in self.potential.connections_by_column_mut(column).iter_mut() {
+                if self.input_scratch[syn.index] {
+                    syn.permanence += self.syn_perm_options.active_inc;
+                } else {
+                    syn.permanence -= self.syn_perm_options.inactive_dec;
+                }
+            }
+            self.potential.update_permanences(column,
+                                              true,
+                                              (self.stimulus_threshold + 0.5) as i32,
+                                              &self.syn_perm_options);
+        }
+        for &input in active {
+            self.input_scratch[input] = false;
+        }
+    }
+}
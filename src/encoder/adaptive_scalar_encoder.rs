@@ -1,6 +1,6 @@
 
 use std::ops::Range;
-use encoder::ScalarEncoder;
+use encoder::{ScalarEncoder, EncoderError};
 use std::collections::VecDeque;
 use std::f64;
 
@@ -17,14 +17,23 @@ impl AdaptiveScalarEncoder {
     }
 
     pub fn new_window(width: usize, size: usize, minmax: Option<Range<f64>>, window_size: usize) -> AdaptiveScalarEncoder {
+        Self::try_new_window(width, size, minmax, window_size).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_new(width: usize, size: usize, minmax: Option<Range<f64>>) -> Result<AdaptiveScalarEncoder, EncoderError> {
+        Self::try_new_window(width, size, minmax, 300)
+    }
+
+    /** `minmax` is the initial range, `0..1` when `None`. The size must leave room for more than `width` bits. */
+    pub fn try_new_window(width: usize, size: usize, minmax: Option<Range<f64>>, window_size: usize) -> Result<AdaptiveScalarEncoder, EncoderError> {
         let minmax_o = minmax.unwrap_or(0.0..1.0);
         let mut encoder = AdaptiveScalarEncoder {
-            encoder: ScalarEncoder::new(width, minmax_o.start, minmax_o.end, size, false),
+            encoder: ScalarEncoder::try_new(width, minmax_o.start, minmax_o.end, size, false)?,
             window: VecDeque::with_capacity(window_size),
         };
-        encoder.encoder.init();
-        encoder
-    }   
+        encoder.encoder.init()?;
+        Ok(encoder)
+    }
 
     pub fn encode_into(&mut self, input: f64, output: &mut [bool]) {
         if !input.is_nan() {
@@ -1,5 +1,5 @@
 use std::ops::Range;
-use encoder::AdaptiveScalarEncoder;
+use encoder::{AdaptiveScalarEncoder, EncoderError};
 use std::collections::VecDeque;
 use std::f64;
 
@@ -13,11 +13,18 @@ impl DeltaEncoder {
     }
 
     pub fn new_window(width: usize, size: usize, minmax: Option<Range<f64>>, window_size: usize) -> DeltaEncoder {
-        let mut encoder = DeltaEncoder {
-            encoder: AdaptiveScalarEncoder::new_window(width, size, minmax, window_size),
-        };
-        encoder
-    }   
+        Self::try_new_window(width, size, minmax, window_size).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_new(width: usize, size: usize, minmax: Option<Range<f64>>) -> Result<DeltaEncoder, EncoderError> {
+        Self::try_new_window(width, size, minmax, 300)
+    }
+
+    pub fn try_new_window(width: usize, size: usize, minmax: Option<Range<f64>>, window_size: usize) -> Result<DeltaEncoder, EncoderError> {
+        Ok(DeltaEncoder {
+            encoder: AdaptiveScalarEncoder::try_new_window(width, size, minmax, window_size)?,
+        })
+    }
 
     pub fn encode(&mut self, input: f64) -> &[bool] {
 		self.encoder.encode(input)
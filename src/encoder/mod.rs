@@ -5,4 +5,92 @@ mod delta_encoder;
 
 pub use self::scalar_encoder::ScalarEncoder;
 pub use self::adaptive_scalar_encoder::AdaptiveScalarEncoder;
-pub use self::delta_encoder::DeltaEncoder;
\ No newline at end of file
+pub use self::delta_encoder::DeltaEncoder;
+
+use std;
+use std::fmt;
+
+#[derive(Debug,Clone,PartialEq)]
+pub enum EncoderError {
+    /** The number of active bits must be odd so they can be centered on a bucket. */
+    EvenWidth(usize),
+    /** `min` or `max` is NaN or infinite. */
+    NonFiniteBounds { min: f64, max: f64 },
+    InvalidRange { min: f64, max: f64 },
+    /** None of size, radius and resolution is positive. */
+    MissingSize,
+    /** The output must have room for more than `width` bits, at least `width` when wrapping. */
+    SizeTooSmall { size: usize, width: usize },
+    OutputSizeMismatch { expected: usize, actual: usize },
+}
+
+impl fmt::Display for EncoderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EncoderError::EvenWidth(width) => write!(f, "width {} must be an odd number (to eliminate centering difficulty)", width),
+            EncoderError::NonFiniteBounds { min, max } => write!(f, "min {} and max {} must be finite", min, max),
+            EncoderError::InvalidRange { min, max } => write!(f, "max {} must be greater than min {}", max, min),
+            EncoderError::MissingSize => write!(f, "one of size, radius and resolution must be positive"),
+            EncoderError::SizeTooSmall { size, width } => write!(f, "size {} is too small for {} active bits", size, width),
+            EncoderError::OutputSizeMismatch { expected, actual } => write!(f, "the encoder writes {} bits, the output has {}", expected, actual),
+        }
+    }
+}
+
+impl std::error::Error for EncoderError {
+    fn description(&self) -> &str {
+        match *self {
+            EncoderError::EvenWidth(_) => "even encoder width",
+            EncoderError::NonFiniteBounds { .. } => "non-finite encoder bounds",
+            EncoderError::InvalidRange { .. } => "invalid encoder range",
+            EncoderError::MissingSize => "missing encoder size",
+            EncoderError::SizeTooSmall { .. } => "encoder size too small",
+            EncoderError::OutputSizeMismatch { .. } => "encoder output size mismatch",
+        }
+    }
+}
+
+/**
+ * Common interface of the scalar encoders, lets regions and models hold any of them.
+ */
+pub trait Encoder {
+    fn encode_into(&mut self, input: f64, output: &mut [bool]);
+    fn get_bucket_index(&mut self, input: f64) -> Option<usize>;
+    fn get_bucket_value(&self, bucket: usize) -> f64;
+    fn size(&self) -> usize;
+
+    /** Same as `encode_into` but an output of the wrong size is an error instead of a panic. */
+    fn try_encode_into(&mut self, input: f64, output: &mut [bool]) -> Result<(), EncoderError> {
+        if output.len() != self.size() {
+            return Err(EncoderError::OutputSizeMismatch { expected: self.size(), actual: output.len() });
+        }
+        self.encode_into(input, output);
+        Ok(())
+    }
+}
+
+macro_rules! impl_encoder {
+    ($name:ident) => {
+        impl Encoder for $name {
+            fn encode_into(&mut self, input: f64, output: &mut [bool]) {
+                $name::encode_into(self, input, output)
+            }
+
+            fn get_bucket_index(&mut self, input: f64) -> Option<usize> {
+                $name::get_bucket_index(self, input)
+            }
+
+            fn get_bucket_value(&self, bucket: usize) -> f64 {
+                $name::get_bucket_value(self, bucket)
+            }
+
+            fn size(&self) -> usize {
+                $name::size(self)
+            }
+        }
+    }
+}
+
+impl_encoder!(ScalarEncoder);
+impl_encoder!(AdaptiveScalarEncoder);
+impl_encoder!(DeltaEncoder);
@@ -1,6 +1,7 @@
 use util::numext::*;
 use std::cmp::PartialOrd;
 use std::ops::{Sub, Add, Mul, Div, Range};
+use encoder::EncoderError;
 
 
 pub struct ScalarEncoder {
@@ -25,38 +26,44 @@ pub struct ScalarEncoder {
 impl ScalarEncoder {
 
     pub fn new(width: usize, min: f64, max: f64, size: usize, wrap: bool) -> ScalarEncoder {
-        let mut encoder = ScalarEncoder::new_intenal(size, width, 0.0, 0.0, min, max, wrap);
-        encoder.init();
-        encoder.internal_size = encoder.size - 2 * encoder.padding;
-        encoder
+        ScalarEncoder::try_new(width, min, max, size, wrap).unwrap_or_else(|err| panic!("{}", err))
     }
 
     pub fn new_with_resolution(width: usize, min: f64, max: f64, resolution: f64, wrap: bool) -> ScalarEncoder {
-        let mut encoder = ScalarEncoder::new_intenal(0, width, 0.0, resolution, min, max, wrap);
-        encoder.init();
-        encoder.internal_size = encoder.size - 2 * encoder.padding;
-        encoder
+        ScalarEncoder::try_new_with_resolution(width, min, max, resolution, wrap).unwrap_or_else(|err| panic!("{}", err))
     }
 
     pub fn new_with_radius(width: usize, min: f64, max: f64, radius: f64, wrap: bool) -> ScalarEncoder {
-        let mut encoder = ScalarEncoder::new_intenal(0, width, radius, 0.0, min, max, wrap);
-        encoder.init();
-        encoder.internal_size = encoder.size - 2 * encoder.padding;
-        encoder
+        ScalarEncoder::try_new_with_radius(width, min, max, radius, wrap).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_new(width: usize, min: f64, max: f64, size: usize, wrap: bool) -> Result<ScalarEncoder, EncoderError> {
+        if size == 0 {
+            return Err(EncoderError::MissingSize);
+        }
+        ScalarEncoder::new_intenal(size, width, 0.0, 0.0, min, max, wrap)
+    }
+
+    pub fn try_new_with_resolution(width: usize, min: f64, max: f64, resolution: f64, wrap: bool) -> Result<ScalarEncoder, EncoderError> {
+        ScalarEncoder::new_intenal(0, width, 0.0, resolution, min, max, wrap)
+    }
+
+    pub fn try_new_with_radius(width: usize, min: f64, max: f64, radius: f64, wrap: bool) -> Result<ScalarEncoder, EncoderError> {
+        ScalarEncoder::new_intenal(0, width, radius, 0.0, min, max, wrap)
     }
 
-    fn new_intenal(size: usize, width: usize, radius: f64, resolution: f64, min: f64, max: f64, wrap: bool) -> ScalarEncoder {
-        if width % 2 == 0 {
-            panic!("width must be an odd number (to eliminate centering difficulty)");
+    fn new_intenal(size: usize, width: usize, radius: f64, resolution: f64, min: f64, max: f64, wrap: bool) -> Result<ScalarEncoder, EncoderError> {
+        if width % 2 != 1 {
+            return Err(EncoderError::EvenWidth(width));
         }
-        if min.is_nan() || max.is_nan() {
-            panic!("min or max are NaN");
+        if !min.is_finite() || !max.is_finite() {
+            return Err(EncoderError::NonFiniteBounds { min, max });
         }
         if min >= max {
-             panic!("maxVal must be > minVal");
+            return Err(EncoderError::InvalidRange { min, max });
         }
         let half_width = (width-1) / 2;
-        ScalarEncoder {
+        let mut encoder = ScalarEncoder {
             size: size,
             internal_size: size,
             width: width,
@@ -71,11 +78,17 @@ impl ScalarEncoder {
             max: max,
             range: max - min,
             internal_range: max - min,
-        }
+        };
+        encoder.init()?;
+        encoder.internal_size = encoder.size - 2 * encoder.padding;
+        Ok(encoder)
     }
 
-    pub(crate) fn init(&mut self) {
+    pub(crate) fn init(&mut self) -> Result<(), EncoderError> {
         if self.size != 0 {
+            if self.size < self.width || (!self.wrap && self.size == self.width) {
+                return Err(EncoderError::SizeTooSmall { size: self.size, width: self.width });
+            }
             self.resolution = if !self.wrap {
                 self.internal_range / (self.size - self.width) as f64
             } else {
@@ -88,12 +101,12 @@ impl ScalarEncoder {
                 self.internal_range + self.resolution
             };
         } else {
-            if self.radius != 0.0  {
+            if self.radius > 0.0  {
                 self.resolution = self.radius / self.width as f64;
-            } else if self.resolution != 0.0 {
+            } else if self.resolution > 0.0 {
                 self.radius = self.resolution * self.width as f64;
             } else {
-                panic!("One of n, radius, resolution must be specified for a ScalarEncoder");
+                return Err(EncoderError::MissingSize);
             }
 
             self.range = if self.wrap {
@@ -105,10 +118,12 @@ impl ScalarEncoder {
             let n = self.width as f64 * self.range / self.radius + 2.0 * self.padding as f64;
             self.size = (n + 0.5) as usize;
         }
+        Ok(())
     }
 
     fn get_encode_range(&self, input: f64) -> Range<isize> {
-        let bucket = self.get_first_on_bit(input).unwrap();
+        // only NaN has no first bit, encode_into_internal leaves the output empty for it
+        let bucket = self.get_first_on_bit(input).unwrap_or(0);
         let mut minbin = bucket;
         let mut maxbin = bucket + 2 * self.half_width as isize;
         minbin..maxbin
@@ -0,0 +1,123 @@
+use std;
+use std::fmt;
+use algo::{SPError, TMError, UPError, CPError, GridError, ClassifierError};
+use encoder::EncoderError;
+use params::ParamsError;
+use model::ModelError;
+
+/**
+ * Any error of the crate. Every algorithm reports its own error type from its `try_`
+ * methods, they all convert into `HtmError` so `?` works across a whole pipeline.
+ */
+#[derive(Debug,Clone,PartialEq)]
+pub enum HtmError {
+    Encoder(EncoderError),
+    SpatialPooler(SPError),
+    TemporalMemory(TMError),
+    UnionPooler(UPError),
+    ColumnPooler(CPError),
+    Grid(GridError),
+    Classifier(ClassifierError),
+    Params(ParamsError),
+    Model(ModelError),
+}
+
+impl fmt::Display for HtmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            HtmError::Encoder(ref err) => write!(f, "encoder: {}", err),
+            HtmError::SpatialPooler(ref err) => write!(f, "spatial pooler: {}", err),
+            HtmError::TemporalMemory(ref err) => write!(f, "temporal memory: {}", err),
+            HtmError::UnionPooler(ref err) => write!(f, "union pooler: {}", err),
+            HtmError::ColumnPooler(ref err) => write!(f, "column pooler: {}", err),
+            HtmError::Grid(ref err) => write!(f, "grid module: {}", err),
+            HtmError::Classifier(ref err) => write!(f, "classifier: {}", err),
+            HtmError::Params(ref err) => write!(f, "parameters: {}", err),
+            HtmError::Model(ref err) => write!(f, "model: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for HtmError {
+    fn description(&self) -> &str {
+        match *self {
+            HtmError::Encoder(_) => "encoder error",
+            HtmError::SpatialPooler(_) => "spatial pooler error",
+            HtmError::TemporalMemory(_) => "temporal memory error",
+            HtmError::UnionPooler(_) => "union pooler error",
+            HtmError::ColumnPooler(_) => "column pooler error",
+            HtmError::Grid(_) => "grid module error",
+            HtmError::Classifier(_) => "classifier error",
+            HtmError::Params(_) => "parameter error",
+            HtmError::Model(_) => "model error",
+        }
+    }
+
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            HtmError::Encoder(ref err) => Some(err),
+            HtmError::SpatialPooler(ref err) => Some(err),
+            HtmError::TemporalMemory(ref err) => Some(err),
+            HtmError::UnionPooler(ref err) => Some(err),
+            HtmError::ColumnPooler(ref err) => Some(err),
+            HtmError::Grid(ref err) => Some(err),
+            HtmError::Classifier(ref err) => Some(err),
+            HtmError::Params(ref err) => Some(err),
+            HtmError::Model(ref err) => Some(err),
+        }
+    }
+}
+
+impl From<EncoderError> for HtmError {
+    fn from(err: EncoderError) -> HtmError {
+        HtmError::Encoder(err)
+    }
+}
+
+impl From<SPError> for HtmError {
+    fn from(err: SPError) -> HtmError {
+        HtmError::SpatialPooler(err)
+    }
+}
+
+impl From<TMError> for HtmError {
+    fn from(err: TMError) -> HtmError {
+        HtmError::TemporalMemory(err)
+    }
+}
+
+impl From<UPError> for HtmError {
+    fn from(err: UPError) -> HtmError {
+        HtmError::UnionPooler(err)
+    }
+}
+
+impl From<CPError> for HtmError {
+    fn from(err: CPError) -> HtmError {
+        HtmError::ColumnPooler(err)
+    }
+}
+
+impl From<GridError> for HtmError {
+    fn from(err: GridError) -> HtmError {
+        HtmError::Grid(err)
+    }
+}
+
+impl From<ClassifierError> for HtmError {
+    fn from(err: ClassifierError) -> HtmError {
+        HtmError::Classifier(err)
+    }
+}
+
+impl From<ParamsError> for HtmError {
+    fn from(err: ParamsError) -> HtmError {
+        HtmError::Params(err)
+    }
+}
+
+impl From<ModelError> for HtmError {
+    fn from(err: ModelError) -> HtmError {
+        HtmError::Model(err)
+    }
+}
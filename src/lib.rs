@@ -4,12 +4,22 @@
 
 
 pub use self::util::{UniversalRng,UniversalNext};
-pub use self::algo::{TemporalMemory,SDRClassifier,Cell,Synapse,Segment, SpatialPooler};
-pub use self::encoder::{ScalarEncoder,DeltaEncoder,AdaptiveScalarEncoder};
+pub use self::algo::{TemporalMemory,TMError,TMState,EvictionPolicy,ForgettingOptions,PruneReport,SDRClassifier,ClassifierError,ActualValue,ClassifierMode,Classification,KNNClassifier,KNNDistance,Prototype,Cell,Synapse,Segment,SegmentRef,SynapseLink,PredictionBranch,ColumnPrediction,ColumnStatistics,SpatialPooler,SpatialPoolerBuilder,SPError,LearningObserver,UnionPooler,UPError,ColumnPooler,CPError,GridModule,GridLocationLayer,GridError};
+pub use self::encoder::{ScalarEncoder,DeltaEncoder,AdaptiveScalarEncoder,Encoder,EncoderError};
+pub use self::network::{Network,Link,LinkKind,NetworkError,Region,RegionData,RegionInputs,Port,DataType,SensorRegion,SPRegion,TMRegion,ClassifierRegion};
+pub use self::model::{HtmPredictionModel,ModelResult,ModelError,raw_anomaly_score};
+pub use self::error::HtmError;
+pub use self::metrics::{ClassificationMetrics,ErrorMetrics,PredictionMetrics};
+pub use self::params::{Params,ParamsError,SpatialPoolerParams,TemporalMemoryParams,ScalarEncoderParams,AdaptiveScalarEncoderParams,EncoderParams};
 
+mod error;
 mod encoder;
 mod util;
 mod algo;
+mod network;
+mod model;
+mod params;
+mod metrics;
 
 extern crate bit_vec;
 extern crate rand;
@@ -17,6 +27,11 @@ extern crate collect_slice;
 extern crate quickersort;
 extern crate rayon;
 extern crate fnv;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate toml;
 
 #[macro_use]
 extern crate log;
@@ -0,0 +1,248 @@
+use std::collections::VecDeque;
+use algo::{ActualValue, Classification};
+
+/**
+ * Online accuracy, confusion matrix and per-class precision and recall of a classifier.
+ * Classes are bucket indices, the matrix grows with the largest class seen.
+ */
+#[derive(Debug,Clone,PartialEq,Default)]
+pub struct ClassificationMetrics {
+    /** `confusion[actual][predicted]` counts the records of every pair of classes. */
+    pub confusion: Vec<Vec<u32>>,
+    /** Records without any prediction per actual class, they count as errors. */
+    pub unpredicted: Vec<u32>,
+    pub total: u32,
+    pub correct: u32,
+}
+
+impl ClassificationMetrics {
+    pub fn new() -> ClassificationMetrics {
+        ClassificationMetrics::default()
+    }
+
+    pub fn record(&mut self, actual: usize, predicted: Option<usize>) {
+        self.total += 1;
+        let predicted = match predicted {
+            Some(predicted) => predicted,
+            None => {
+                self.grow(actual);
+                self.unpredicted[actual] += 1;
+                return;
+            }
+        };
+        self.grow(actual.max(predicted));
+        self.confusion[actual][predicted] += 1;
+        if actual == predicted {
+            self.correct += 1;
+        }
+    }
+
+    fn grow(&mut self, class: usize) {
+        if class >= self.confusion.len() {
+            for row in &mut self.confusion {
+                row.resize(class + 1, 0);
+            }
+            self.confusion.resize(class + 1, vec![0; class + 1]);
+            self.unpredicted.resize(class + 1, 0);
+        }
+    }
+
+    pub fn num_classes(&self) -> usize {
+        self.confusion.len()
+    }
+
+    /** Fraction of the records predicted correctly, `None` before the first record. */
+    pub fn accuracy(&self) -> Option<f64> {
+        if self.total == 0 {
+            None
+        } else {
+            Some(f64::from(self.correct) / f64::from(self.total))
+        }
+    }
+
+    /** Fraction of the predictions of `class` that were right, `None` if it was never predicted. */
+    pub fn precision(&self, class: usize) -> Option<f64> {
+        let predicted: u32 = self.confusion.iter().filter_map(|row| row.get(class)).sum();
+        if predicted == 0 {
+            return None;
+        }
+        Some(f64::from(self.confusion[class][class]) / f64::from(predicted))
+    }
+
+    /** Fraction of the records of `class` that were predicted as `class`, `None` if it never occurred. */
+    pub fn recall(&self, class: usize) -> Option<f64> {
+        let row = self.confusion.get(class)?;
+        let occurrences = row.iter().sum::<u32>() + self.unpredicted[class];
+        if occurrences == 0 {
+            return None;
+        }
+        Some(f64::from(row[class]) / f64::from(occurrences))
+    }
+}
+
+/**
+ * Errors of numeric predictions over the last `window` records: MAPE, altMAPE, RMSE,
+ * NRMSE and the negative log-likelihood of the actual bucket. MAPE and altMAPE are
+ * percentages like in NuPIC.
+ */
+#[derive(Debug,Clone,PartialEq)]
+pub struct ErrorMetrics {
+    pub window: usize,
+    /** `(actual, predicted)` pairs, the oldest first. */
+    predictions: VecDeque<(f64, f64)>,
+    /** Probability given to the actual bucket, the oldest first. */
+    likelihoods: VecDeque<f32>,
+}
+
+/** Probabilities are clipped to this before the log-likelihood, a zero would be infinite. */
+const MIN_LIKELIHOOD: f32 = 1e-6;
+
+impl ErrorMetrics {
+    pub fn new(window: usize) -> ErrorMetrics {
+        ErrorMetrics {
+            window,
+            predictions: VecDeque::with_capacity(window),
+            likelihoods: VecDeque::with_capacity(window),
+        }
+    }
+
+    pub fn record(&mut self, actual: f64, predicted: f64) {
+        if self.predictions.len() == self.window {
+            self.predictions.pop_front();
+        }
+        if self.window > 0 {
+            self.predictions.push_back((actual, predicted));
+        }
+    }
+
+    /** Records the probability the prediction gave to the actual bucket. */
+    pub fn record_likelihood(&mut self, probability: f32) {
+        if self.likelihoods.len() == self.window {
+            self.likelihoods.pop_front();
+        }
+        if self.window > 0 {
+            self.likelihoods.push_back(probability);
+        }
+    }
+
+    /** Mean absolute error relative to the actual value, records with a zero actual value are skipped. */
+    pub fn mape(&self) -> Option<f64> {
+        let errors: Vec<f64> = self.predictions.iter()
+            .filter(|&&(actual, _)| actual != 0.0)
+            .map(|&(actual, predicted)| ((actual - predicted) / actual).abs())
+            .collect();
+        mean(errors.iter().cloned()).map(|error| error * 100.0)
+    }
+
+    /** Sum of the absolute errors relative to the sum of the absolute actual values. */
+    pub fn alt_mape(&self) -> Option<f64> {
+        let actual: f64 = self.predictions.iter().map(|&(actual, _)| actual.abs()).sum();
+        if actual == 0.0 {
+            return None;
+        }
+        let errors: f64 = self.predictions.iter().map(|&(actual, predicted)| (actual - predicted).abs()).sum();
+        Some(errors / actual * 100.0)
+    }
+
+    pub fn rmse(&self) -> Option<f64> {
+        mean(self.predictions.iter().map(|&(actual, predicted)| (actual - predicted).powi(2))).map(f64::sqrt)
+    }
+
+    /** RMSE relative to the standard deviation of the actual values, `None` when they are constant. */
+    pub fn nrmse(&self) -> Option<f64> {
+        let average = mean(self.predictions.iter().map(|&(actual, _)| actual))?;
+        let deviation = mean(self.predictions.iter().map(|&(actual, _)| (actual - average).powi(2)))?.sqrt();
+        if deviation == 0.0 {
+            return None;
+        }
+        self.rmse().map(|rmse| rmse / deviation)
+    }
+
+    /** Mean negative natural log of the probabilities given to the actual buckets. */
+    pub fn nll(&self) -> Option<f64> {
+        mean(self.likelihoods.iter().map(|&probability| -f64::from(probability.max(MIN_LIKELIHOOD)).ln()))
+    }
+}
+
+fn mean<I: Iterator<Item = f64>>(values: I) -> Option<f64> {
+    let (sum, count) = values.fold((0.0, 0), |(sum, count), value| (sum + value, count + 1));
+    if count == 0 {
+        None
+    } else {
+        Some(sum / f64::from(count))
+    }
+}
+
+/** What a classification predicted for one step, kept until the actual value is known. */
+#[derive(Debug,Clone)]
+struct PendingPrediction {
+    bucket: Option<usize>,
+    value: Option<f64>,
+    distribution: Vec<f32>,
+}
+
+/**
+ * Evaluates the predictions of an `SDRClassifier` per step. The prediction of step `n`
+ * is kept for `n` records and compared with the actual bucket and value of the record
+ * it predicted, so every metric measures a real prediction. Numeric metrics are only
+ * recorded for values with `ActualValue::as_f64`.
+ */
+#[derive(Debug,Clone)]
+pub struct PredictionMetrics {
+    pub steps: Vec<usize>,
+    /** Classification metrics per step, in the order of `steps`. */
+    pub classification: Vec<ClassificationMetrics>,
+    /** Error metrics per step, in the order of `steps`. */
+    pub errors: Vec<ErrorMetrics>,
+    pending: Vec<VecDeque<PendingPrediction>>,
+}
+
+impl PredictionMetrics {
+    /** Metrics of `steps`, the error metrics use the last `window` evaluated predictions. */
+    pub fn new(steps: Vec<usize>, window: usize) -> PredictionMetrics {
+        PredictionMetrics {
+            classification: vec![ClassificationMetrics::new(); steps.len()],
+            errors: vec![ErrorMetrics::new(window); steps.len()],
+            pending: steps.iter().map(|&step| VecDeque::with_capacity(step + 1)).collect(),
+            steps,
+        }
+    }
+
+    /**
+     * Records the actual bucket and value of this record together with the classification
+     * computed from it, in the same order as the `SDRClassifier::compute` calls. A step
+     * missing from the classification counts as no prediction.
+     */
+    pub fn record<T: ActualValue>(&mut self, bucket: usize, actual: &T, classification: &Classification<T>) {
+        let actual_value = actual.as_f64();
+        for (index, &step) in self.steps.iter().enumerate() {
+            self.pending[index].push_back(PendingPrediction {
+                bucket: classification.most_probable_bucket(step),
+                value: classification.most_probable_value(step).and_then(|value| value.as_f64()),
+                distribution: classification.stats(step).map(|dist| dist.to_vec()).unwrap_or_default(),
+            });
+            if self.pending[index].len() <= step {
+                continue;
+            }
+
+            // the prediction made `step` records ago is about this record
+            let prediction = self.pending[index].pop_front().unwrap();
+            self.classification[index].record(bucket, prediction.bucket);
+            if let (Some(actual), Some(predicted)) = (actual_value, prediction.value) {
+                self.errors[index].record(actual, predicted);
+            }
+            if !prediction.distribution.is_empty() {
+                let probability = prediction.distribution.get(bucket).cloned().unwrap_or(0.0);
+                self.errors[index].record_likelihood(probability);
+            }
+        }
+    }
+
+    pub fn classification_metrics(&self, step: usize) -> Option<&ClassificationMetrics> {
+        self.steps.iter().position(|&s| s == step).map(|index| &self.classification[index])
+    }
+
+    pub fn error_metrics(&self, step: usize) -> Option<&ErrorMetrics> {
+        self.steps.iter().position(|&s| s == step).map(|index| &self.errors[index])
+    }
+}
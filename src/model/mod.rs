@@ -0,0 +1,223 @@
+use std;
+use std::cmp;
+use std::fmt;
+use algo::{SpatialPooler, SPError, TemporalMemory, SDRClassifier};
+use encoder::Encoder;
+use error::HtmError;
+
+#[derive(Debug,Clone,PartialEq)]
+pub enum ModelError {
+    /** A record needs one value per encoder. */
+    RecordSizeMismatch { expected: usize, actual: usize },
+}
+
+impl fmt::Display for ModelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ModelError::RecordSizeMismatch { expected, actual } => write!(f, "a record needs {} values, one per encoder, got {}", expected, actual),
+        }
+    }
+}
+
+impl std::error::Error for ModelError {
+    fn description(&self) -> &str {
+        match *self {
+            ModelError::RecordSizeMismatch { .. } => "record size mismatch",
+        }
+    }
+}
+
+/** Everything `HtmPredictionModel::run` infers from one record. */
+#[derive(Debug,Clone)]
+pub struct ModelResult {
+    pub record_num: u32,
+    /** Value of the predicted field in this record. */
+    pub actual_value: f64,
+    /** Bucket of the predicted field's value, `None` when its encoder can not encode it. */
+    pub bucket_index: Option<usize>,
+    /** Fraction of the active columns that were not predicted by the previous record. */
+    pub anomaly_score: f32,
+    /** Probability of every bucket, per prediction step. */
+    pub multi_step_bucket_likelihoods: Vec<(usize, Vec<f32>)>,
+    /** Predicted values with their probability per step, the most probable first. */
+    pub multi_step_predictions: Vec<(usize, Vec<(f64, f32)>)>,
+    /** The most probable value per step, `None` until the classifier learned something. */
+    pub multi_step_best_predictions: Vec<(usize, Option<f64>)>,
+    /** The bucket values weighted by their probability per step, smoother than the best prediction. */
+    pub multi_step_weighted_predictions: Vec<(usize, Option<f64>)>,
+    /** The value the classifier associates with each bucket. */
+    pub bucket_values: Vec<Option<f64>>,
+}
+
+impl ModelResult {
+    pub fn best_prediction(&self, step: usize) -> Option<f64> {
+        self.multi_step_best_predictions.iter()
+            .find(|&&(s, _)| s == step)
+            .and_then(|&(_, value)| value)
+    }
+}
+
+/**
+ * Streaming prediction and anomaly model in the spirit of NuPIC's OPF HTMPredictionModel:
+ * the fields of a record are encoded side by side, fed through a `SpatialPooler` and a
+ * `TemporalMemory`, and an `SDRClassifier` predicts the `predicted_field` from the active cells.
+ *
+ * Parameters are public, change them between `new` and `init`.
+ */
+pub struct HtmPredictionModel {
+    pub encoders: Vec<Box<dyn Encoder>>,
+    pub predicted_field: usize,
+    pub sp: SpatialPooler,
+    pub tm: TemporalMemory,
+    pub classifier: SDRClassifier<f64>,
+    /** Learning of all the algorithms, turn it off to only infer. */
+    pub learn: bool,
+
+    record_num: u32,
+    input: Vec<bool>,
+    prev_predicted_columns: Vec<usize>,
+}
+
+impl HtmPredictionModel {
+    /** A model with 2048 columns of 32 cells, NuPIC's defaults. */
+    pub fn new(encoders: Vec<Box<dyn Encoder>>, predicted_field: usize, steps: Vec<usize>) -> HtmPredictionModel {
+        HtmPredictionModel::new_with_size(encoders, predicted_field, steps, 2048, 32)
+    }
+
+    pub fn new_with_size(encoders: Vec<Box<dyn Encoder>>, predicted_field: usize, steps: Vec<usize>, columns: usize, cells_per_column: usize) -> HtmPredictionModel {
+        assert!(predicted_field < encoders.len(), "predicted field {} has no encoder", predicted_field);
+        let input_width: usize = encoders.iter().map(|encoder| encoder.size()).sum();
+
+        let mut sp = SpatialPooler::new(vec![input_width], vec![columns]);
+        sp.potential_radius = input_width as i32;
+        sp.potential_pct = 0.8;
+        sp.global_inhibition = true;
+        sp.num_active_columns_per_inh_area = cmp::max(1, columns / 50) as f64;
+        sp.syn_perm_options.connected = 0.1;
+        sp.syn_perm_options.active_inc = 0.05;
+        sp.syn_perm_options.inactive_dec = 0.005;
+        sp.max_boost = 1.0;
+
+        let mut tm = TemporalMemory::new(columns as u32, cells_per_column as u32);
+        tm.activation_threshold = 13;
+        tm.min_threshold = 10;
+        tm.max_new_synapse_count = 20;
+        tm.initial_permanence = 0.21;
+        tm.connected_permanence = 0.5;
+        tm.permanence_increment = 0.1;
+        tm.permanence_decrement = 0.1;
+
+        let classifier = SDRClassifier::new(steps, 0.1, 0.3, columns * cells_per_column);
+
+        HtmPredictionModel {
+            encoders,
+            predicted_field,
+            sp,
+            tm,
+            classifier,
+            learn: true,
+            record_num: 0,
+            input: vec![false; input_width],
+            prev_predicted_columns: Vec::new(),
+        }
+    }
+
+    /** Initializes the spatial pooler, call it once the parameters are set. */
+    #[must_use = "an invalid spatial pooler stays uninitialized"]
+    pub fn init(&mut self) -> Result<(), SPError> {
+        self.sp.init()
+    }
+
+    /** Forgets the current sequence, e.g. at the start of a new one. */
+    pub fn reset(&mut self) {
+        self.tm.reset();
+        self.prev_predicted_columns.clear();
+    }
+
+    /** Feeds one record, a value per encoder, and returns what the model inferred from it. Panics when `try_run` fails. */
+    pub fn run(&mut self, record: &[f64]) -> ModelResult {
+        match self.try_run(record) {
+            Ok(result) => result,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /**
+     * Same as `run` but a record of the wrong length, an uninitialized model and the
+     * errors of the encoders and algorithms are reported.
+     */
+    pub fn try_run(&mut self, record: &[f64]) -> Result<ModelResult, HtmError> {
+        if record.len() != self.encoders.len() {
+            return Err(ModelError::RecordSizeMismatch { expected: self.encoders.len(), actual: record.len() }.into());
+        }
+
+        let mut offset = 0;
+        for (encoder, &value) in self.encoders.iter_mut().zip(record.iter()) {
+            let size = encoder.size();
+            let output = &mut self.input[offset..offset + size];
+            for bit in output.iter_mut() {
+                *bit = false;
+            }
+            encoder.try_encode_into(value, output)?;
+            offset += size;
+        }
+
+        self.sp.try_compute(&self.input, self.learn)?;
+        let mut active_columns = self.sp.winner_columns.clone();
+        active_columns.sort();
+
+        let anomaly_score = raw_anomaly_score(&active_columns, &self.prev_predicted_columns);
+
+        self.tm.try_compute(&active_columns, self.learn)?;
+        self.prev_predicted_columns = self.tm.state.segments_active.iter().map(|seg| seg.segment.cell.column as usize).collect();
+        self.prev_predicted_columns.dedup();
+
+        let mut pattern: Vec<usize> = self.tm.state.active_cells.iter().map(|cell| cell.index(self.tm.cells) as usize).collect();
+        pattern.sort();
+
+        let actual_value = record[self.predicted_field];
+        let bucket_index = self.encoders[self.predicted_field].get_bucket_index(actual_value);
+        let (bucket, learn) = match bucket_index {
+            Some(bucket) => (bucket, self.learn),
+            None => (0, false),
+        };
+        let classification = self.classifier.try_compute(self.record_num, bucket, actual_value, &pattern, learn, true)?;
+
+        let mut predictions = Vec::with_capacity(classification.distributions.len());
+        let mut best = Vec::with_capacity(classification.distributions.len());
+        let mut weighted = Vec::with_capacity(classification.distributions.len());
+        for &(step, ref dist) in &classification.distributions {
+            let mut values: Vec<(f64, f32)> = dist.iter()
+                .enumerate()
+                .filter_map(|(bucket, &probability)| classification.actual_value(bucket).map(|&value| (value, probability)))
+                .collect();
+            values.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(cmp::Ordering::Equal));
+            best.push((step, values.first().map(|&(value, _)| value)));
+            weighted.push((step, classification.weighted_value(step)));
+            predictions.push((step, values));
+        }
+
+        let result = ModelResult {
+            record_num: self.record_num,
+            actual_value,
+            bucket_index,
+            anomaly_score,
+            multi_step_bucket_likelihoods: classification.distributions,
+            multi_step_predictions: predictions,
+            multi_step_best_predictions: best,
+            multi_step_weighted_predictions: weighted,
+            bucket_values: classification.actual_values,
+        };
+        self.record_num += 1;
+        Ok(result)
+    }
+}
+
+/** Fraction of `active` columns missing from the sorted `predicted` columns, 0 without active columns. */
+pub fn raw_anomaly_score(active: &[usize], predicted: &[usize]) -> f32 {
+    if active.is_empty() {
+        return 0.0;
+    }
+    let predicted_active = active.iter().filter(|column| predicted.binary_search(column).is_ok()).count();
+    (active.len() - predicted_active) as f32 / active.len() as f32
+}
@@ -0,0 +1,259 @@
+mod region;
+mod regions;
+
+pub use self::region::{Region, RegionData, RegionInputs, Port, DataType};
+pub use self::regions::{SensorRegion, SPRegion, TMRegion, ClassifierRegion};
+
+use std;
+use std::fmt;
+use error::HtmError;
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum LinkKind {
+    /** The destination reads the output of the source computed in the same step. */
+    Forward,
+    /** The destination reads the output of the source from the previous step. */
+    Feedback,
+}
+
+#[derive(Debug,Clone,PartialEq)]
+pub struct Link {
+    pub source: String,
+    pub output: &'static str,
+    pub destination: String,
+    pub input: &'static str,
+    pub kind: LinkKind,
+}
+
+#[derive(Debug,Clone,PartialEq)]
+pub enum NetworkError {
+    DuplicateRegion(String),
+    UnknownRegion(String),
+    UnknownOutput { region: String, output: String },
+    UnknownInput { region: String, input: String },
+    TypeMismatch { output: DataType, input: DataType },
+    /** A forward link whose source runs after its destination, use a feedback link. */
+    OutOfOrder { source: String, destination: String },
+    /** Only SDR inputs can take several links. */
+    InputAlreadyLinked { region: String, input: String },
+    /** A region failed to compute, the step was aborted. */
+    Region { region: String, error: HtmError },
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            NetworkError::DuplicateRegion(ref name) => write!(f, "a region named {} already exists", name),
+            NetworkError::UnknownRegion(ref name) => write!(f, "no region named {}", name),
+            NetworkError::UnknownOutput { ref region, ref output } => write!(f, "region {} has no output {}", region, output),
+            NetworkError::UnknownInput { ref region, ref input } => write!(f, "region {} has no input {}", region, input),
+            NetworkError::TypeMismatch { output, input } => write!(f, "can not link a {:?} output to a {:?} input", output, input),
+            NetworkError::OutOfOrder { ref source, ref destination } => write!(f, "{} runs after {}, link them with a feedback link", source, destination),
+            NetworkError::InputAlreadyLinked { ref region, ref input } => write!(f, "input {} of region {} is already linked", input, region),
+            NetworkError::Region { ref region, ref error } => write!(f, "region {} failed: {}", region, error),
+        }
+    }
+}
+
+impl std::error::Error for NetworkError {
+    fn description(&self) -> &str {
+        match *self {
+            NetworkError::DuplicateRegion(_) => "duplicate region",
+            NetworkError::UnknownRegion(_) => "unknown region",
+            NetworkError::UnknownOutput { .. } => "unknown output",
+            NetworkError::UnknownInput { .. } => "unknown input",
+            NetworkError::TypeMismatch { .. } => "link type mismatch",
+            NetworkError::OutOfOrder { .. } => "forward link out of order",
+            NetworkError::InputAlreadyLinked { .. } => "input already linked",
+            NetworkError::Region { .. } => "region failed",
+        }
+    }
+
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            NetworkError::Region { ref error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+struct RegionEntry {
+    name: String,
+    phase: u32,
+    region: Box<dyn Region>,
+}
+
+/**
+ * A set of named regions connected by links. Every step runs the regions by
+ * increasing phase, regions of the same phase in the order they were added,
+ * each one receiving the outputs linked to its inputs.
+ */
+pub struct Network {
+    regions: Vec<RegionEntry>,
+    links: Vec<Link>,
+    pub learn: bool,
+    pub iteration: u64,
+}
+
+impl Network {
+    pub fn new() -> Network {
+        Network {
+            regions: Vec::new(),
+            links: Vec::new(),
+            learn: true,
+            iteration: 0,
+        }
+    }
+
+    /** Adds a region in phase 0. */
+    pub fn add_region<R: Region + 'static>(&mut self, name: &str, region: R) -> Result<(), NetworkError> {
+        self.add_region_with_phase(name, 0, region)
+    }
+
+    pub fn add_region_with_phase<R: Region + 'static>(&mut self, name: &str, phase: u32, region: R) -> Result<(), NetworkError> {
+        if self.position(name).is_some() {
+            return Err(NetworkError::DuplicateRegion(name.to_string()));
+        }
+        let index = self.regions.iter().position(|entry| entry.phase > phase).unwrap_or(self.regions.len());
+        self.regions.insert(index, RegionEntry {
+            name: name.to_string(),
+            phase,
+            region: Box::new(region),
+        });
+        Ok(())
+    }
+
+    /** Links `output` of `source` to `input` of `destination` within the same step. */
+    pub fn link(&mut self, source: &str, output: &str, destination: &str, input: &str) -> Result<(), NetworkError> {
+        self.add_link(source, output, destination, input, LinkKind::Forward)
+    }
+
+    /** Links `output` of `source` to `input` of `destination`, delayed by one step. */
+    pub fn link_feedback(&mut self, source: &str, output: &str, destination: &str, input: &str) -> Result<(), NetworkError> {
+        self.add_link(source, output, destination, input, LinkKind::Feedback)
+    }
+
+    fn add_link(&mut self, source: &str, output: &str, destination: &str, input: &str, kind: LinkKind) -> Result<(), NetworkError> {
+        let source_index = self.position(source).ok_or_else(|| NetworkError::UnknownRegion(source.to_string()))?;
+        let destination_index = self.position(destination).ok_or_else(|| NetworkError::UnknownRegion(destination.to_string()))?;
+
+        let output_port = self.regions[source_index].region.outputs().into_iter()
+            .find(|port| port.name == output)
+            .ok_or_else(|| NetworkError::UnknownOutput { region: source.to_string(), output: output.to_string() })?;
+        let input_port = self.regions[destination_index].region.inputs().into_iter()
+            .find(|port| port.name == input)
+            .ok_or_else(|| NetworkError::UnknownInput { region: destination.to_string(), input: input.to_string() })?;
+
+        if output_port.data_type != input_port.data_type {
+            return Err(NetworkError::TypeMismatch { output: output_port.data_type, input: input_port.data_type });
+        }
+        if kind == LinkKind::Forward && source_index >= destination_index {
+            return Err(NetworkError::OutOfOrder { source: source.to_string(), destination: destination.to_string() });
+        }
+        if input_port.data_type != DataType::Sdr && self.links.iter().any(|link| link.destination == destination && link.input == input) {
+            return Err(NetworkError::InputAlreadyLinked { region: destination.to_string(), input: input.to_string() });
+        }
+
+        self.links.push(Link {
+            source: source.to_string(),
+            output: output_port.name,
+            destination: destination.to_string(),
+            input: input_port.name,
+            kind,
+        });
+        Ok(())
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.regions.iter().position(|entry| entry.name == name)
+    }
+
+    pub fn links(&self) -> &[Link] {
+        &self.links
+    }
+
+    /** Region names in execution order. */
+    pub fn region_names(&self) -> Vec<&str> {
+        self.regions.iter().map(|entry| entry.name.as_str()).collect()
+    }
+
+    pub fn region(&self, name: &str) -> Option<&dyn Region> {
+        self.position(name).map(|index| &*self.regions[index].region)
+    }
+
+    pub fn region_mut(&mut self, name: &str) -> Option<&mut (dyn Region + 'static)> {
+        match self.position(name) {
+            Some(index) => Some(&mut *self.regions[index].region),
+            None => None,
+        }
+    }
+
+    /** Current value of an output. */
+    pub fn output(&self, region: &str, output: &str) -> Option<RegionData> {
+        self.region(region).and_then(|region| region.output(output))
+    }
+
+    /**
+     * Runs every region once. Returns false when a region reported that it has no
+     * more data, the regions after it are not run and the step does not count.
+     * A region that fails aborts the step the same way and its error is returned.
+     */
+    pub fn step(&mut self) -> Result<bool, NetworkError> {
+        let feedback: Vec<Option<RegionData>> = self.links.iter()
+            .map(|link| match link.kind {
+                LinkKind::Feedback => self.output(&link.source, link.output),
+                LinkKind::Forward => None,
+            })
+            .collect();
+
+        let mut inputs = RegionInputs::new();
+        for index in 0..self.regions.len() {
+            inputs.clear();
+            for (link, delayed) in self.links.iter().zip(feedback.iter()) {
+                if link.destination != self.regions[index].name {
+                    continue;
+                }
+                let data = match link.kind {
+                    LinkKind::Feedback => delayed.clone(),
+                    LinkKind::Forward => self.output(&link.source, link.output),
+                };
+                if let Some(data) = data {
+                    inputs.push(link.input, data);
+                }
+            }
+            let entry = &mut self.regions[index];
+            match entry.region.compute(&inputs, self.learn) {
+                Ok(true) => {}
+                Ok(false) => return Ok(false),
+                Err(error) => return Err(NetworkError::Region { region: entry.name.clone(), error }),
+            }
+        }
+        self.iteration += 1;
+        Ok(true)
+    }
+
+    /**
+     * Runs up to `steps` steps, stops early once a region runs out of data. Returns the
+     * steps run, or the error of the first region that failed.
+     */
+    pub fn run(&mut self, steps: usize) -> Result<usize, NetworkError> {
+        for step in 0..steps {
+            if !self.step()? {
+                return Ok(step);
+            }
+        }
+        Ok(steps)
+    }
+
+    pub fn reset(&mut self) {
+        for entry in &mut self.regions {
+            entry.region.reset();
+        }
+    }
+}
+
+impl Default for Network {
+    fn default() -> Network {
+        Network::new()
+    }
+}
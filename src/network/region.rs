@@ -0,0 +1,120 @@
+use fnv::FnvHashMap;
+use error::HtmError;
+
+/** Kind of data carried by an input or output of a region. */
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum DataType {
+    /** Sorted active bit indices of a binary vector. */
+    Sdr,
+    /** A raw value together with its encoder bucket, what classifiers learn from. */
+    Scalar,
+    /** Probability distributions over buckets, one per prediction step. */
+    Probabilities,
+}
+
+#[derive(Debug,Clone,PartialEq)]
+pub enum RegionData {
+    Sdr { width: usize, active: Vec<usize> },
+    Scalar { bucket: usize, value: f64 },
+    Probabilities(Vec<(usize, Vec<f32>)>),
+}
+
+impl RegionData {
+    pub fn data_type(&self) -> DataType {
+        match *self {
+            RegionData::Sdr { .. } => DataType::Sdr,
+            RegionData::Scalar { .. } => DataType::Scalar,
+            RegionData::Probabilities(_) => DataType::Probabilities,
+        }
+    }
+
+    pub fn empty_sdr(width: usize) -> RegionData {
+        RegionData::Sdr { width, active: Vec::new() }
+    }
+}
+
+/** A named input or output of a region. */
+#[derive(Debug,Clone,PartialEq)]
+pub struct Port {
+    pub name: &'static str,
+    pub data_type: DataType,
+}
+
+impl Port {
+    pub fn new(name: &'static str, data_type: DataType) -> Port {
+        Port { name, data_type }
+    }
+}
+
+/**
+ * The data linked to the inputs of a region for one step.
+ * SDRs of several links into the same input are concatenated in link order.
+ */
+#[derive(Debug,Default)]
+pub struct RegionInputs {
+    data: FnvHashMap<&'static str, RegionData>,
+}
+
+impl RegionInputs {
+    pub fn new() -> RegionInputs {
+        Default::default()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&RegionData> {
+        self.data.get(name)
+    }
+
+    /** The active bits and the width of an SDR input. */
+    pub fn sdr(&self, name: &str) -> Option<(&[usize], usize)> {
+        match self.data.get(name) {
+            Some(&RegionData::Sdr { width, ref active }) => Some((active, width)),
+            _ => None,
+        }
+    }
+
+    pub fn scalar(&self, name: &str) -> Option<(usize, f64)> {
+        match self.data.get(name) {
+            Some(&RegionData::Scalar { bucket, value }) => Some((bucket, value)),
+            _ => None,
+        }
+    }
+
+    /** Adds `data` to the input, appending it to an SDR that is already there. */
+    pub fn push(&mut self, name: &'static str, data: RegionData) {
+        match (self.data.remove(name), data) {
+            (Some(RegionData::Sdr { width, mut active }), RegionData::Sdr { width: other_width, active: other }) => {
+                active.extend(other.iter().map(|&bit| bit + width));
+                self.data.insert(name, RegionData::Sdr { width: width + other_width, active });
+            }
+            (_, data) => {
+                self.data.insert(name, data);
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.data.clear();
+    }
+}
+
+/**
+ * A node of a `Network`. Regions declare their inputs and outputs so links can be
+ * checked when the network is built, and must be able to report every output at
+ * any time, before their first `compute` as an empty value of the right size.
+ */
+pub trait Region {
+    fn inputs(&self) -> Vec<Port>;
+    fn outputs(&self) -> Vec<Port>;
+
+    /**
+     * Runs one step on the linked `inputs`. Returns false when the region has no more
+     * data to produce (e.g. an exhausted sensor), which stops `Network::run`, and an
+     * error when the inputs do not fit the wrapped algorithm.
+     */
+    fn compute(&mut self, inputs: &RegionInputs, learn: bool) -> Result<bool, HtmError>;
+
+    fn output(&self, name: &str) -> Option<RegionData>;
+
+    /** Forgets the sequence state, learned connections are kept. */
+    fn reset(&mut self) {}
+}
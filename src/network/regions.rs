@@ -0,0 +1,234 @@
+use fnv::FnvHashSet;
+use algo::{SpatialPooler, SPError, TemporalMemory, SDRClassifier, Cell};
+use error::HtmError;
+use encoder::Encoder;
+use network::{Region, RegionInputs, RegionData, Port, DataType};
+
+/**
+ * Feeds the values of an iterator through an encoder.
+ * Outputs `encoded` (SDR) and `value` (scalar with its bucket, once a value was read).
+ */
+pub struct SensorRegion {
+    pub encoder: Box<dyn Encoder>,
+    source: Box<dyn Iterator<Item = f64>>,
+    encoded: Vec<bool>,
+    value: Option<(usize, f64)>,
+}
+
+impl SensorRegion {
+    pub fn new<E, I>(encoder: E, source: I) -> SensorRegion
+        where E: Encoder + 'static,
+              I: Iterator<Item = f64> + 'static
+    {
+        let size = encoder.size();
+        SensorRegion {
+            encoder: Box::new(encoder),
+            source: Box::new(source),
+            encoded: vec![false; size],
+            value: None,
+        }
+    }
+
+    /** The last value read from the source. */
+    pub fn value(&self) -> Option<f64> {
+        self.value.map(|(_, value)| value)
+    }
+}
+
+impl Region for SensorRegion {
+    fn inputs(&self) -> Vec<Port> {
+        Vec::new()
+    }
+
+    fn outputs(&self) -> Vec<Port> {
+        vec![Port::new("encoded", DataType::Sdr), Port::new("value", DataType::Scalar)]
+    }
+
+    fn compute(&mut self, _inputs: &RegionInputs, _learn: bool) -> Result<bool, HtmError> {
+        let value = match self.source.next() {
+            Some(value) => value,
+            None => return Ok(false),
+        };
+        for bit in &mut self.encoded {
+            *bit = false;
+        }
+        self.encoder.try_encode_into(value, &mut self.encoded)?;
+        self.value = self.encoder.get_bucket_index(value).map(|bucket| (bucket, value));
+        Ok(true)
+    }
+
+    fn output(&self, name: &str) -> Option<RegionData> {
+        match name {
+            "encoded" => Some(RegionData::Sdr {
+                width: self.encoded.len(),
+                active: self.encoded.iter().enumerate().filter(|&(_, &bit)| bit).map(|(index, _)| index).collect(),
+            }),
+            "value" => self.value.map(|(bucket, value)| RegionData::Scalar { bucket, value }),
+            _ => None,
+        }
+    }
+}
+
+/** Wraps an initialized `SpatialPooler`. Input `bottom_up`, output `active_columns`. */
+pub struct SPRegion {
+    pub sp: SpatialPooler,
+    input: Vec<bool>,
+    active_columns: Vec<usize>,
+}
+
+impl SPRegion {
+    pub fn new(sp: SpatialPooler) -> SPRegion {
+        SPRegion {
+            input: vec![false; sp.num_inputs],
+            sp,
+            active_columns: Vec::new(),
+        }
+    }
+}
+
+impl Region for SPRegion {
+    fn inputs(&self) -> Vec<Port> {
+        vec![Port::new("bottom_up", DataType::Sdr)]
+    }
+
+    fn outputs(&self) -> Vec<Port> {
+        vec![Port::new("active_columns", DataType::Sdr)]
+    }
+
+    fn compute(&mut self, inputs: &RegionInputs, learn: bool) -> Result<bool, HtmError> {
+        for bit in &mut self.input {
+            *bit = false;
+        }
+        if let Some((active, width)) = inputs.sdr("bottom_up") {
+            if width != self.input.len() {
+                return Err(SPError::InputSizeMismatch { expected: self.input.len(), actual: width }.into());
+            }
+            for &bit in active {
+                self.input[bit] = true;
+            }
+        }
+        self.sp.try_compute(&self.input, learn)?;
+        self.active_columns.clone_from(&self.sp.winner_columns);
+        self.active_columns.sort();
+        Ok(true)
+    }
+
+    fn output(&self, name: &str) -> Option<RegionData> {
+        match name {
+            "active_columns" => Some(RegionData::Sdr { width: self.sp.num_columns, active: self.active_columns.clone() }),
+            _ => None,
+        }
+    }
+}
+
+/**
+ * Wraps a `TemporalMemory`. Input `bottom_up` are the active columns, the optional
+ * `external` SDR is fed through `compute_external`, bit `i` being the cell
+ * `Cell { column: columns + i, cell: 0 }`.
+ * Outputs `active_cells`, `predicted_active_cells` (cell indices) and `predicted_columns`.
+ */
+pub struct TMRegion {
+    pub tm: TemporalMemory,
+}
+
+impl TMRegion {
+    pub fn new(tm: TemporalMemory) -> TMRegion {
+        TMRegion { tm }
+    }
+
+    fn cells_sdr(&self, cells: &FnvHashSet<Cell>) -> RegionData {
+        let mut active: Vec<usize> = cells.iter().map(|cell| cell.index(self.tm.cells) as usize).collect();
+        active.sort();
+        RegionData::Sdr { width: self.tm.num_cells(), active }
+    }
+}
+
+impl Region for TMRegion {
+    fn inputs(&self) -> Vec<Port> {
+        vec![Port::new("bottom_up", DataType::Sdr), Port::new("external", DataType::Sdr)]
+    }
+
+    fn outputs(&self) -> Vec<Port> {
+        vec![Port::new("active_cells", DataType::Sdr),
+             Port::new("predicted_active_cells", DataType::Sdr),
+             Port::new("predicted_columns", DataType::Sdr)]
+    }
+
+    fn compute(&mut self, inputs: &RegionInputs, learn: bool) -> Result<bool, HtmError> {
+        let columns = inputs.sdr("bottom_up").map(|(active, _)| active).unwrap_or(&[]);
+        let first_external = self.tm.columns;
+        let external: FnvHashSet<Cell> = inputs.sdr("external")
+            .map(|(active, _)| active)
+            .unwrap_or(&[])
+            .iter()
+            .map(|&bit| Cell { column: first_external + bit as u32, cell: 0 })
+            .collect();
+        self.tm.try_compute_external(columns, &external, learn)?;
+        Ok(true)
+    }
+
+    fn output(&self, name: &str) -> Option<RegionData> {
+        match name {
+            "active_cells" => Some(self.cells_sdr(&self.tm.state.active_cells)),
+            "predicted_active_cells" => Some(self.cells_sdr(&self.tm.state.predicted_active_cells)),
+            "predicted_columns" => {
+                let mut active: Vec<usize> = self.tm.state.segments_active.iter().map(|seg| seg.segment.cell.column as usize).collect();
+                active.dedup();
+                Some(RegionData::Sdr { width: self.tm.num_columns(), active })
+            }
+            _ => None,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.tm.reset();
+    }
+}
+
+/**
+ * Wraps an `SDRClassifier`. Learns the `value` input from the `pattern` SDR and
+ * outputs `probabilities`, one distribution per prediction step.
+ */
+pub struct ClassifierRegion {
+    pub classifier: SDRClassifier<f64>,
+    record: u32,
+    probabilities: Vec<(usize, Vec<f32>)>,
+}
+
+impl ClassifierRegion {
+    pub fn new(classifier: SDRClassifier<f64>) -> ClassifierRegion {
+        ClassifierRegion {
+            classifier,
+            record: 0,
+            probabilities: Vec::new(),
+        }
+    }
+}
+
+impl Region for ClassifierRegion {
+    fn inputs(&self) -> Vec<Port> {
+        vec![Port::new("pattern", DataType::Sdr), Port::new("value", DataType::Scalar)]
+    }
+
+    fn outputs(&self) -> Vec<Port> {
+        vec![Port::new("probabilities", DataType::Probabilities)]
+    }
+
+    fn compute(&mut self, inputs: &RegionInputs, learn: bool) -> Result<bool, HtmError> {
+        let pattern = inputs.sdr("pattern").map(|(active, _)| active).unwrap_or(&[]);
+        let (bucket, value, learn) = match inputs.scalar("value") {
+            Some((bucket, value)) => (bucket, value, learn),
+            None => (0, 0.0, false),
+        };
+        self.probabilities = self.classifier.try_compute(self.record, bucket, value, pattern, learn, true)?.distributions;
+        self.record += 1;
+        Ok(true)
+    }
+
+    fn output(&self, name: &str) -> Option<RegionData> {
+        match name {
+            "probabilities" => Some(RegionData::Probabilities(self.probabilities.clone())),
+            _ => None,
+        }
+    }
+}
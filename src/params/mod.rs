@@ -0,0 +1,484 @@
+use std;
+use std::fmt;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+use serde::{Serialize, Deserializer};
+use serde::de::DeserializeOwned;
+use serde_json;
+use toml;
+use algo::{SpatialPooler, SPError, TemporalMemory, EvictionPolicy};
+use encoder::{Encoder, EncoderError, ScalarEncoder, AdaptiveScalarEncoder, DeltaEncoder};
+use util::UniversalRng;
+
+#[derive(Debug,Clone,PartialEq)]
+pub enum ParamsError {
+    Io(String),
+    /** Malformed JSON, a wrong value type or an unknown key. */
+    Json(String),
+    /** Malformed TOML, a wrong value type or an unknown key. */
+    Toml(String),
+    /** The file extension is neither `json` nor `toml`. */
+    UnknownFormat(String),
+    /** The parameters were read but can not build the algorithm. */
+    Invalid(String),
+}
+
+impl fmt::Display for ParamsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParamsError::Io(ref err) => write!(f, "can not access the parameter file: {}", err),
+            ParamsError::Json(ref err) => write!(f, "invalid JSON parameters: {}", err),
+            ParamsError::Toml(ref err) => write!(f, "invalid TOML parameters: {}", err),
+            ParamsError::UnknownFormat(ref path) => write!(f, "{} is neither a .json nor a .toml file", path),
+            ParamsError::Invalid(ref reason) => write!(f, "invalid parameters: {}", reason),
+        }
+    }
+}
+
+impl From<EncoderError> for ParamsError {
+    fn from(err: EncoderError) -> ParamsError {
+        ParamsError::Invalid(err.to_string())
+    }
+}
+
+impl std::error::Error for ParamsError {
+    fn description(&self) -> &str {
+        match *self {
+            ParamsError::Io(_) => "parameter file error",
+            ParamsError::Json(_) => "invalid JSON parameters",
+            ParamsError::Toml(_) => "invalid TOML parameters",
+            ParamsError::UnknownFormat(_) => "unknown parameter file format",
+            ParamsError::Invalid(_) => "invalid parameters",
+        }
+    }
+}
+
+/**
+ * Reading and writing of a parameter struct as JSON or TOML.
+ * Keys missing from a file take their default value, unknown keys are an error.
+ */
+pub trait Params: Serialize + DeserializeOwned {
+    fn from_json(json: &str) -> Result<Self, ParamsError> {
+        serde_json::from_str(json).map_err(|err| ParamsError::Json(err.to_string()))
+    }
+
+    fn to_json(&self) -> Result<String, ParamsError> {
+        serde_json::to_string_pretty(self).map_err(|err| ParamsError::Json(err.to_string()))
+    }
+
+    fn from_toml(text: &str) -> Result<Self, ParamsError> {
+        toml::from_str(text).map_err(|err| ParamsError::Toml(err.to_string()))
+    }
+
+    fn to_toml(&self) -> Result<String, ParamsError> {
+        toml::to_string(self).map_err(|err| ParamsError::Toml(err.to_string()))
+    }
+
+    /** Reads a `.json` or `.toml` file. */
+    fn load<P: AsRef<Path>>(path: P) -> Result<Self, ParamsError> {
+        let path = path.as_ref();
+        let format = Format::of(path)?;
+        let mut text = String::new();
+        File::open(path)
+            .and_then(|mut file| file.read_to_string(&mut text))
+            .map_err(|err| ParamsError::Io(err.to_string()))?;
+        match format {
+            Format::Json => Self::from_json(&text),
+            Format::Toml => Self::from_toml(&text),
+        }
+    }
+
+    /** Writes a `.json` or `.toml` file. */
+    fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), ParamsError> {
+        let path = path.as_ref();
+        let text = match Format::of(path)? {
+            Format::Json => self.to_json()?,
+            Format::Toml => self.to_toml()?,
+        };
+        File::create(path)
+            .and_then(|mut file| file.write_all(text.as_bytes()))
+            .map_err(|err| ParamsError::Io(err.to_string()))
+    }
+}
+
+enum Format {
+    Json,
+    Toml,
+}
+
+impl Format {
+    fn of(path: &Path) -> Result<Format, ParamsError> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => Ok(Format::Json),
+            Some("toml") => Ok(Format::Toml),
+            _ => Err(ParamsError::UnknownFormat(path.display().to_string())),
+        }
+    }
+}
+
+/** Dimensions given as a single number, like the OPF `inputWidth`, or as a list. */
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Dimensions {
+    Flat(usize),
+    Shape(Vec<usize>),
+}
+
+fn deserialize_dimensions<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<usize>, D::Error> {
+    Ok(match serde::Deserialize::deserialize(deserializer)? {
+        Dimensions::Flat(size) => vec![size],
+        Dimensions::Shape(shape) => shape,
+    })
+}
+
+/**
+ * Parameters of a `SpatialPooler`, the defaults are the ones of `SpatialPooler::new`
+ * except for NuPIC's global inhibition with 40 active columns, which `new` leaves unset,
+ * so the defaults build a pooler. The dimensions default to NuPIC's 32x32 inputs and
+ * 64x64 columns.
+ * NuPIC's camelCase names (`potentialPct`, `synPermConnected`, ...) are accepted as well,
+ * and so are the OPF `inputWidth` and `columnCount` for one-dimensional poolers.
+ * NuPIC's `boostStrength` is rejected as an unknown key: it is the exponent of NuPIC's
+ * exponential boosting, not the maximum boost of this pooler.
+ */
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SpatialPoolerParams {
+    #[serde(alias = "inputDimensions", alias = "inputWidth", deserialize_with = "deserialize_dimensions")]
+    pub input_dimensions: Vec<usize>,
+    #[serde(alias = "columnDimensions", alias = "columnCount", deserialize_with = "deserialize_dimensions")]
+    pub column_dimensions: Vec<usize>,
+    #[serde(alias = "potentialRadius")]
+    pub potential_radius: i32,
+    #[serde(alias = "potentialPct")]
+    pub potential_pct: f64,
+    #[serde(alias = "globalInhibition")]
+    pub global_inhibition: bool,
+    #[serde(alias = "localAreaDensity")]
+    pub local_area_density: f64,
+    #[serde(alias = "numActiveColumnsPerInhArea")]
+    pub num_active_columns_per_inh_area: f64,
+    #[serde(alias = "stimulusThreshold")]
+    pub stimulus_threshold: f32,
+    #[serde(alias = "synPermInactiveDec")]
+    pub syn_perm_inactive_dec: f32,
+    #[serde(alias = "synPermActiveInc")]
+    pub syn_perm_active_inc: f32,
+    #[serde(alias = "synPermConnected")]
+    pub syn_perm_connected: f32,
+    #[serde(alias = "synPermMin")]
+    pub syn_perm_min: f32,
+    #[serde(alias = "synPermMax")]
+    pub syn_perm_max: f32,
+    #[serde(alias = "minPctOverlapDutyCycle", alias = "minPctOverlapDutyCycles")]
+    pub min_pct_overlap_duty_cycles: f32,
+    #[serde(alias = "minPctActiveDutyCycle", alias = "minPctActiveDutyCycles")]
+    pub min_pct_active_duty_cycles: f32,
+    #[serde(alias = "dutyCyclePeriod")]
+    pub duty_cycle_period: u32,
+    #[serde(alias = "maxBoost")]
+    pub max_boost: f32,
+    #[serde(alias = "wrapAround")]
+    pub wrap_around: bool,
+    #[serde(alias = "initConnectedPct")]
+    pub init_connected_pct: f32,
+    #[serde(alias = "updatePeriod")]
+    pub update_period: u32,
+    pub seed: u32,
+}
+
+impl Default for SpatialPoolerParams {
+    fn default() -> SpatialPoolerParams {
+        SpatialPoolerParams {
+            input_dimensions: vec![32, 32],
+            column_dimensions: vec![64, 64],
+            potential_radius: 16,
+            potential_pct: 0.5,
+            global_inhibition: true,
+            local_area_density: -1.0,
+            num_active_columns_per_inh_area: 40.0,
+            stimulus_threshold: 0.0,
+            syn_perm_inactive_dec: 0.008,
+            syn_perm_active_inc: 0.05,
+            syn_perm_connected: 0.10,
+            syn_perm_min: 0.0,
+            syn_perm_max: 1.0,
+            min_pct_overlap_duty_cycles: 0.001,
+            min_pct_active_duty_cycles: 0.001,
+            duty_cycle_period: 1000,
+            max_boost: 10.0,
+            wrap_around: true,
+            init_connected_pct: 0.5,
+            update_period: 50,
+            seed: 42,
+        }
+    }
+}
+
+impl Params for SpatialPoolerParams {}
+
+impl SpatialPoolerParams {
+    /** An initialized pooler with these parameters, see `SpatialPooler::validate` for the errors. */
+    pub fn build(&self) -> Result<SpatialPooler, SPError> {
+        let mut sp = SpatialPooler::new(self.input_dimensions.clone(), self.column_dimensions.clone());
+        sp.potential_radius = self.potential_radius;
+        sp.potential_pct = self.potential_pct;
+        sp.global_inhibition = self.global_inhibition;
+        sp.local_area_density = self.local_area_density;
+        sp.num_active_columns_per_inh_area = self.num_active_columns_per_inh_area;
+        sp.stimulus_threshold = self.stimulus_threshold;
+        sp.syn_perm_options.inactive_dec = self.syn_perm_inactive_dec;
+        sp.syn_perm_options.active_inc = self.syn_perm_active_inc;
+        sp.syn_perm_options.connected = self.syn_perm_connected;
+        sp.syn_perm_options.min = self.syn_perm_min;
+        sp.syn_perm_options.max = self.syn_perm_max;
+        sp.min_pct_overlap_duty_cycles = self.min_pct_overlap_duty_cycles;
+        sp.min_pct_active_duty_cycles = self.min_pct_active_duty_cycles;
+        sp.duty_cycle_period = self.duty_cycle_period;
+        sp.max_boost = self.max_boost;
+        sp.wrap_around = self.wrap_around;
+        sp.init_connected_pct = self.init_connected_pct;
+        sp.update_period = self.update_period;
+        sp.rand = UniversalRng::from_seed([self.seed, 0, 0, 0]);
+        sp.seed = self.seed;
+        sp.init()?;
+        Ok(sp)
+    }
+}
+
+impl From<&SpatialPooler> for SpatialPoolerParams {
+    /** The current parameters of `sp` with the seed it was created from. */
+    fn from(sp: &SpatialPooler) -> SpatialPoolerParams {
+        SpatialPoolerParams {
+            input_dimensions: sp.input_dimensions.clone(),
+            column_dimensions: sp.column_dimensions.clone(),
+            potential_radius: sp.potential_radius,
+            potential_pct: sp.potential_pct,
+            global_inhibition: sp.global_inhibition,
+            local_area_density: sp.local_area_density,
+            num_active_columns_per_inh_area: sp.num_active_columns_per_inh_area,
+            stimulus_threshold: sp.stimulus_threshold,
+            syn_perm_inactive_dec: sp.syn_perm_options.inactive_dec,
+            syn_perm_active_inc: sp.syn_perm_options.active_inc,
+            syn_perm_connected: sp.syn_perm_options.connected,
+            syn_perm_min: sp.syn_perm_options.min,
+            syn_perm_max: sp.syn_perm_options.max,
+            min_pct_overlap_duty_cycles: sp.min_pct_overlap_duty_cycles,
+            min_pct_active_duty_cycles: sp.min_pct_active_duty_cycles,
+            duty_cycle_period: sp.duty_cycle_period,
+            max_boost: sp.max_boost,
+            wrap_around: sp.wrap_around,
+            init_connected_pct: sp.init_connected_pct,
+            update_period: sp.update_period,
+            seed: sp.seed,
+        }
+    }
+}
+
+/**
+ * Parameters of a `TemporalMemory`, the defaults are the ones of `TemporalMemory::new`
+ * with NuPIC's 2048 columns of 32 cells. NuPIC's camelCase names (`cellsPerColumn`, `initialPerm`, ...) are accepted as well.
+ */
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TemporalMemoryParams {
+    #[serde(alias = "columnCount")]
+    pub columns: u32,
+    #[serde(alias = "cellsPerColumn")]
+    pub cells_per_column: u32,
+    #[serde(alias = "activationThreshold")]
+    pub activation_threshold: u32,
+    #[serde(alias = "minThreshold")]
+    pub min_threshold: u32,
+    #[serde(alias = "maxNewSynapseCount", alias = "newSynapseCount")]
+    pub max_new_synapse_count: u32,
+    #[serde(alias = "maxSegmentsPerCell")]
+    pub max_segments_per_cell: u32,
+    #[serde(alias = "maxSynapsesPerSegment")]
+    pub max_synapses_per_segment: u32,
+    #[serde(alias = "segmentEviction")]
+    pub segment_eviction: EvictionPolicy,
+    #[serde(alias = "initialPermanence", alias = "initialPerm")]
+    pub initial_permanence: f32,
+    #[serde(alias = "connectedPermanence")]
+    pub connected_permanence: f32,
+    #[serde(alias = "permanenceIncrement", alias = "permanenceInc")]
+    pub permanence_increment: f32,
+    #[serde(alias = "permanenceDecrement", alias = "permanenceDec")]
+    pub permanence_decrement: f32,
+    #[serde(alias = "predictedSegmentDecrement")]
+    pub predicted_segment_decrement: f32,
+    #[serde(alias = "parallelDendrites")]
+    pub parallel_dendrites: bool,
+    pub seed: u32,
+}
+
+impl Default for TemporalMemoryParams {
+    fn default() -> TemporalMemoryParams {
+        TemporalMemoryParams {
+            columns: 2048,
+            cells_per_column: 32,
+            activation_threshold: 13,
+            min_threshold: 10,
+            max_new_synapse_count: 20,
+            max_segments_per_cell: 255,
+            max_synapses_per_segment: 255,
+            segment_eviction: EvictionPolicy::LeastRecentlyUsed,
+            initial_permanence: 0.21,
+            connected_permanence: 0.5,
+            permanence_increment: 0.1,
+            permanence_decrement: 0.1,
+            predicted_segment_decrement: 0.0,
+            parallel_dendrites: false,
+            seed: 42,
+        }
+    }
+}
+
+impl Params for TemporalMemoryParams {}
+
+impl TemporalMemoryParams {
+    pub fn build(&self) -> TemporalMemory {
+        let mut tm = TemporalMemory::new(self.columns, self.cells_per_column);
+        tm.activation_threshold = self.activation_threshold;
+        tm.min_threshold = self.min_threshold;
+        tm.max_new_synapse_count = self.max_new_synapse_count;
+        tm.max_segments_per_cell = self.max_segments_per_cell;
+        tm.max_synapses_per_segment = self.max_synapses_per_segment;
+        tm.segment_eviction = self.segment_eviction;
+        tm.initial_permanence = self.initial_permanence;
+        tm.connected_permanence = self.connected_permanence;
+        tm.permanence_increment = self.permanence_increment;
+        tm.permanence_decrement = self.permanence_decrement;
+        tm.predicted_segment_decrement = self.predicted_segment_decrement;
+        tm.parallel_dendrites = self.parallel_dendrites;
+        tm.rand = UniversalRng::from_seed([self.seed, 0, 0, 0]);
+        tm.seed = self.seed;
+        tm
+    }
+}
+
+impl From<&TemporalMemory> for TemporalMemoryParams {
+    /** The current parameters of `tm` with the seed it was created from. */
+    fn from(tm: &TemporalMemory) -> TemporalMemoryParams {
+        TemporalMemoryParams {
+            columns: tm.columns,
+            cells_per_column: tm.cells,
+            activation_threshold: tm.activation_threshold,
+            min_threshold: tm.min_threshold,
+            max_new_synapse_count: tm.max_new_synapse_count,
+            max_segments_per_cell: tm.max_segments_per_cell,
+            max_synapses_per_segment: tm.max_synapses_per_segment,
+            segment_eviction: tm.segment_eviction,
+            initial_permanence: tm.initial_permanence,
+            connected_permanence: tm.connected_permanence,
+            permanence_increment: tm.permanence_increment,
+            permanence_decrement: tm.permanence_decrement,
+            predicted_segment_decrement: tm.predicted_segment_decrement,
+            parallel_dendrites: tm.parallel_dendrites,
+            seed: tm.seed,
+        }
+    }
+}
+
+/**
+ * Parameters of a `ScalarEncoder`, exactly one of `size`, `resolution` and `radius` must be set.
+ * NuPIC's names (`w`, `n`, `minval`, `maxval`, `periodic`) are accepted as well.
+ */
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScalarEncoderParams {
+    #[serde(alias = "w")]
+    pub width: usize,
+    #[serde(alias = "minval")]
+    pub min: f64,
+    #[serde(alias = "maxval")]
+    pub max: f64,
+    #[serde(default, alias = "n", skip_serializing_if = "Option::is_none")]
+    pub size: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolution: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub radius: Option<f64>,
+    #[serde(default, alias = "periodic")]
+    pub wrap: bool,
+}
+
+impl ScalarEncoderParams {
+    pub fn build(&self) -> Result<ScalarEncoder, ParamsError> {
+        let encoder = match (self.size, self.resolution, self.radius) {
+            (Some(size), None, None) => ScalarEncoder::try_new(self.width, self.min, self.max, size, self.wrap),
+            (None, Some(resolution), None) => ScalarEncoder::try_new_with_resolution(self.width, self.min, self.max, resolution, self.wrap),
+            (None, None, Some(radius)) => ScalarEncoder::try_new_with_radius(self.width, self.min, self.max, radius, self.wrap),
+            _ => return Err(ParamsError::Invalid("set exactly one of size, resolution and radius".to_string())),
+        };
+        encoder.map_err(ParamsError::from)
+    }
+}
+
+/**
+ * Parameters of an `AdaptiveScalarEncoder` or a `DeltaEncoder`, the range adapts to
+ * the last `window_size` values starting from `min..max` (`0..1` when not set).
+ */
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AdaptiveScalarEncoderParams {
+    #[serde(alias = "w")]
+    pub width: usize,
+    #[serde(alias = "n")]
+    pub size: usize,
+    #[serde(default, alias = "minval", skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    #[serde(default, alias = "maxval", skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+    #[serde(default = "default_window_size", alias = "windowSize")]
+    pub window_size: usize,
+}
+
+fn default_window_size() -> usize {
+    300
+}
+
+impl AdaptiveScalarEncoderParams {
+    fn range(&self) -> Result<Option<std::ops::Range<f64>>, ParamsError> {
+        match (self.min, self.max) {
+            (None, None) => Ok(None),
+            (Some(min), Some(max)) => Ok(Some(min..max)),
+            _ => Err(ParamsError::Invalid("set both min and max or neither".to_string())),
+        }
+    }
+
+    pub fn build(&self) -> Result<AdaptiveScalarEncoder, ParamsError> {
+        let range = self.range()?;
+        AdaptiveScalarEncoder::try_new_window(self.width, self.size, range, self.window_size).map_err(ParamsError::from)
+    }
+
+    pub fn build_delta(&self) -> Result<DeltaEncoder, ParamsError> {
+        let range = self.range()?;
+        DeltaEncoder::try_new_window(self.width, self.size, range, self.window_size).map_err(ParamsError::from)
+    }
+}
+
+/**
+ * Parameters of any encoder, tagged with the encoder `type` (NuPIC's class name).
+ */
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
+#[serde(tag = "type")]
+pub enum EncoderParams {
+    ScalarEncoder(ScalarEncoderParams),
+    AdaptiveScalarEncoder(AdaptiveScalarEncoderParams),
+    DeltaEncoder(AdaptiveScalarEncoderParams),
+}
+
+impl Params for EncoderParams {}
+
+impl EncoderParams {
+    pub fn build(&self) -> Result<Box<dyn Encoder>, ParamsError> {
+        Ok(match *self {
+            EncoderParams::ScalarEncoder(ref params) => Box::new(params.build()?),
+            EncoderParams::AdaptiveScalarEncoder(ref params) => Box::new(params.build()?),
+            EncoderParams::DeltaEncoder(ref params) => Box::new(params.build_delta()?),
+        })
+    }
+}
@@ -0,0 +1,117 @@
+extern crate htm;
+extern crate fnv;
+
+use htm::*;
+use fnv::FnvHashSet;
+
+fn feature(index: u32) -> FnvHashSet<Cell> {
+    (0..20).map(|i| Cell { column: index * 20 + i, cell: 0 }).collect()
+}
+
+fn create_pooler(lateral_inputs: usize) -> ColumnPooler {
+    ColumnPooler::new(512, 20, lateral_inputs)
+}
+
+fn learn_object(pooler: &mut ColumnPooler, features: &[u32]) -> Vec<Cell> {
+    pooler.reset();
+    for _ in 0..3 {
+        for &f in features {
+            let input = feature(f);
+            pooler.compute(&input, &input, &[], true);
+        }
+    }
+    pooler.active_cells.clone()
+}
+
+#[test]
+fn new_objects_get_fresh_sdrs() {
+    let mut pooler = create_pooler(0);
+    let a = learn_object(&mut pooler, &[0, 1]);
+    let b = learn_object(&mut pooler, &[0, 2]);
+
+    assert_eq!(a.len(), 20);
+    assert_eq!(b.len(), 20);
+    assert!(a.iter().all(|cell| cell.column == 0 && cell.cell < 512));
+    assert!(a.iter().filter(|cell| b.binary_search(cell).is_ok()).count() < 5);
+    assert!(!pooler.internal_segments(a[0]).is_empty());
+    assert!(pooler.proximal_segment(a[0]).synapses.len() >= 40);
+}
+
+#[test]
+fn sensations_narrow_down_the_object() {
+    let mut pooler = create_pooler(0);
+    let a = learn_object(&mut pooler, &[0, 1]);
+    let b = learn_object(&mut pooler, &[0, 2]);
+    let c = learn_object(&mut pooler, &[3, 1]);
+
+    pooler.reset();
+    pooler.compute(&feature(0), &FnvHashSet::default(), &[], false);
+    // the shared feature activates both objects containing it
+    assert!(a.iter().all(|cell| pooler.active_cells.binary_search(cell).is_ok()));
+    assert!(b.iter().all(|cell| pooler.active_cells.binary_search(cell).is_ok()));
+    assert!(c.iter().filter(|cell| pooler.active_cells.binary_search(cell).is_ok()).count() < 5);
+
+    pooler.compute(&feature(1), &FnvHashSet::default(), &[], false);
+    let narrowed = pooler.active_cells.clone();
+    assert!(a.iter().all(|cell| narrowed.binary_search(cell).is_ok()));
+    // only cells shared by B and C can also be supported by both sensations
+    for cell in narrowed.iter().filter(|cell| a.binary_search(cell).is_err()) {
+        assert!(b.binary_search(cell).is_ok() && c.binary_search(cell).is_ok());
+    }
+
+    // inertia keeps the best supported cells when the next sensation is unknown
+    pooler.compute(&feature(5), &FnvHashSet::default(), &[], false);
+    assert_eq!(pooler.active_cells, a);
+}
+
+#[test]
+fn lateral_input_disambiguates() {
+    let mut left = create_pooler(1);
+    let mut right = create_pooler(1);
+    right.set_column(1);
+
+    // object A: left senses 0 while right senses 1, object B: left senses 0 while right senses 2
+    let mut reps = Vec::new();
+    for &(l, r) in &[(0, 1), (0, 2)] {
+        left.reset();
+        right.reset();
+        for _ in 0..3 {
+            let (left_prev, right_prev) = (left.active_cells.clone(), right.active_cells.clone());
+            left.compute(&feature(l), &feature(l), &[&right_prev], true);
+            right.compute(&feature(r), &feature(r), &[&left_prev], true);
+        }
+        reps.push((left.active_cells.clone(), right.active_cells.clone()));
+    }
+    assert!(right.active_cells.iter().all(|cell| cell.column == 1));
+
+    left.reset();
+    right.reset();
+    left.compute(&feature(0), &FnvHashSet::default(), &[&[]], false);
+    right.compute(&feature(2), &FnvHashSet::default(), &[&[]], false);
+    assert!(reps.iter().all(|rep| rep.0.iter().all(|cell| left.active_cells.binary_search(cell).is_ok())));
+    assert_eq!(right.active_cells, reps[1].1);
+
+    let right_prev = right.active_cells.clone();
+    left.compute(&feature(0), &FnvHashSet::default(), &[&right_prev], false);
+    assert_eq!(left.active_cells, reps[1].0);
+
+    // the lateral cells can come in any order
+    let mut reversed = right_prev.clone();
+    reversed.reverse();
+    left.reset();
+    left.compute(&feature(0), &FnvHashSet::default(), &[&[]], false);
+    assert_eq!(left.distal_support(&[], &[&right_prev]), left.distal_support(&[], &[&reversed]));
+    left.compute(&feature(0), &FnvHashSet::default(), &[&reversed], false);
+    assert_eq!(left.active_cells, reps[1].0);
+}
+
+#[test]
+fn rejects_a_wrong_number_of_lateral_inputs() {
+    let mut pooler = create_pooler(1);
+    let input = feature(0);
+    assert_eq!(Err(CPError::LateralInputMismatch { expected: 1, actual: 0 }), pooler.try_compute(&input, &input, &[], true));
+    assert_eq!(0, pooler.iteration);
+    assert!(pooler.active_cells.is_empty());
+    assert_eq!(Ok(()), pooler.try_compute(&input, &input, &[&[]], true));
+    assert_eq!(20, pooler.active_cells.len());
+}
@@ -0,0 +1,119 @@
+extern crate htm;
+
+use htm::*;
+
+#[test]
+fn encoder_constructors_report_invalid_parameters() {
+    assert_eq!(Some(EncoderError::EvenWidth(20)), ScalarEncoder::try_new(20, 0.0, 1.0, 100, false).err());
+    assert_eq!(Some(EncoderError::InvalidRange { min: 1.0, max: 1.0 }), ScalarEncoder::try_new(21, 1.0, 1.0, 100, false).err());
+    assert!(match ScalarEncoder::try_new(21, std::f64::NAN, 1.0, 100, false) {
+        Err(EncoderError::NonFiniteBounds { .. }) => true,
+        _ => false,
+    });
+    let infinity = std::f64::INFINITY;
+    assert_eq!(Some(EncoderError::NonFiniteBounds { min: 0.0, max: infinity }), ScalarEncoder::try_new(21, 0.0, infinity, 100, false).err());
+    assert_eq!(Some(EncoderError::NonFiniteBounds { min: -infinity, max: 1.0 }), ScalarEncoder::try_new_with_resolution(21, -infinity, 1.0, 0.1, false).err());
+    assert_eq!(Some(EncoderError::NonFiniteBounds { min: -infinity, max: infinity }), ScalarEncoder::try_new_with_radius(21, -infinity, infinity, 1.0, false).err());
+    assert_eq!(Some(EncoderError::MissingSize), ScalarEncoder::try_new_with_resolution(21, 0.0, 1.0, 0.0, false).err());
+    assert_eq!(Some(EncoderError::SizeTooSmall { size: 21, width: 21 }), ScalarEncoder::try_new(21, 0.0, 1.0, 21, false).err());
+    assert!(ScalarEncoder::try_new(21, 0.0, 1.0, 21, true).is_ok());
+    assert_eq!(Some(EncoderError::SizeTooSmall { size: 10, width: 21 }), AdaptiveScalarEncoder::try_new(21, 10, None).err());
+    assert_eq!(Some(EncoderError::EvenWidth(4)), DeltaEncoder::try_new(4, 100, None).err());
+}
+
+#[test]
+fn encoders_do_not_panic_on_bad_input() {
+    let mut encoder = ScalarEncoder::try_new(21, 0.0, 10.0, 100, false).unwrap();
+    let mut output = vec![true; 100];
+    encoder.encode_into(std::f64::NAN, &mut output);
+    assert!(output.iter().all(|&bit| !bit));
+
+    let mut short = vec![false; 50];
+    assert_eq!(Err(EncoderError::OutputSizeMismatch { expected: 100, actual: 50 }), encoder.try_encode_into(5.0, &mut short));
+    assert_eq!(Ok(()), encoder.try_encode_into(5.0, &mut output));
+    assert_eq!(21, output.iter().filter(|&&bit| bit).count());
+}
+
+#[test]
+fn classifier_reports_invalid_configuration_and_input() {
+    assert_eq!(Some(ClassifierError::NoSteps), SDRClassifier::<f64>::try_new(vec![], 0.1, 0.3, 10).err());
+    assert_eq!(Some(ClassifierError::DuplicateStep(1)), SDRClassifier::<f64>::try_new(vec![0, 1, 1], 0.1, 0.3, 10).err());
+    assert_eq!(Some(ClassifierError::InvalidAlpha(0.0)), SDRClassifier::<f64>::try_new(vec![0], 0.0, 0.3, 10).err());
+    assert_eq!(Some(ClassifierError::ActValueAlphaOutOfRange(1.5)), SDRClassifier::<f64>::try_new(vec![0], 0.1, 1.5, 10).err());
+    assert_eq!(Some(ClassifierError::EmptyInput), SDRClassifier::<f64>::try_new(vec![0], 0.1, 0.3, 0).err());
+    // `new` keeps accepting the parameters it always took
+    let mut lenient = SDRClassifier::<f64>::new(vec![0, 0], 0.0, 1.5, 10);
+    assert_eq!(vec![0, 0], lenient.compute(0, 1, 2.0, &[1, 2], true, true).steps());
+
+    let mut classifier = SDRClassifier::<f64>::try_new(vec![0, 1], 0.1, 0.3, 10).unwrap();
+    assert!(classifier.try_compute(5, 0, 1.0, &[1, 2], true, true).is_ok());
+    assert_eq!(Some(ClassifierError::RecordOutOfOrder { record_num: 4, previous: 5 }),
+               classifier.try_compute(4, 0, 1.0, &[1, 2], true, true).err());
+    assert!(classifier.try_compute(6, 1, 2.0, &[3, 4], true, true).is_ok());
+    assert_eq!(None, classifier.get_value(7));
+}
+
+#[test]
+fn spatial_pooler_and_temporal_memory_report_bad_input() {
+    let mut uninitialized = SpatialPooler::new(vec![20], vec![64]);
+    assert_eq!(Err(SPError::NotInitialized), uninitialized.try_compute(&[false; 20], true));
+
+    let mut sp = SpatialPooler::builder(vec![20], vec![64])
+        .full_potential_radius()
+        .global_inhibition(true)
+        .num_active_columns_per_inh_area(4.0)
+        .build()
+        .unwrap();
+    assert_eq!(Err(SPError::InputSizeMismatch { expected: 20, actual: 10 }), sp.try_compute(&[false; 10], true));
+    assert_eq!(Ok(()), sp.try_compute(&[true; 20], true));
+
+    assert_eq!(Some(TMError::ZeroSize { columns: 64, cells: 0 }), TemporalMemory::try_new(64, 0).err());
+}
+
+fn pipeline(value: f64, input_width: usize) -> Result<usize, HtmError> {
+    let mut encoder = ScalarEncoder::try_new(21, 0.0, 10.0, 100, false)?;
+    let mut input = vec![false; input_width];
+    encoder.try_encode_into(value, &mut input)?;
+
+    let mut sp = SpatialPooler::builder(vec![100], vec![64])
+        .full_potential_radius()
+        .global_inhibition(true)
+        .num_active_columns_per_inh_area(4.0)
+        .build()?;
+    sp.try_compute(&input, true)?;
+    let mut columns = sp.winner_columns.clone();
+    columns.sort();
+
+    let mut tm = TemporalMemory::try_new(64, 4)?;
+    tm.try_compute(&columns, true)?;
+    Ok(tm.state.active_cells.len())
+}
+
+#[test]
+fn errors_convert_into_htm_error() {
+    assert_eq!(Ok(16), pipeline(5.0, 100));
+    match pipeline(5.0, 99) {
+        Err(HtmError::Encoder(EncoderError::OutputSizeMismatch { expected: 100, actual: 99 })) => {}
+        other => panic!("unexpected {:?}", other),
+    }
+    let err = HtmError::from(TMError::DuplicateColumn(3));
+    assert_eq!("temporal memory: active column 3 appears more than once", err.to_string());
+}
+
+#[test]
+fn network_and_model_report_region_errors() {
+    let uninitialized = SpatialPooler::new(vec![100], vec![64]);
+    let mut net = Network::new();
+    net.add_region("sensor", SensorRegion::new(ScalarEncoder::new(21, 0.0, 10.0, 100, false), vec![1.0, 2.0].into_iter())).unwrap();
+    net.add_region("sp", SPRegion::new(uninitialized)).unwrap();
+    net.link("sensor", "encoded", "sp", "bottom_up").unwrap();
+    assert_eq!(Err(NetworkError::Region { region: "sp".to_string(), error: HtmError::SpatialPooler(SPError::NotInitialized) }), net.run(2));
+    assert_eq!(0, net.iteration);
+
+    let encoder = ScalarEncoder::new(21, 0.0, 10.0, 100, false);
+    let mut model = HtmPredictionModel::new_with_size(vec![Box::new(encoder)], 0, vec![1], 256, 4);
+    assert_eq!(Some(HtmError::SpatialPooler(SPError::NotInitialized)), model.try_run(&[1.0]).err());
+    model.init().unwrap();
+    assert_eq!(Some(HtmError::Model(ModelError::RecordSizeMismatch { expected: 1, actual: 2 })), model.try_run(&[1.0, 2.0]).err());
+    assert_eq!(0, model.try_run(&[1.0]).unwrap().record_num);
+}
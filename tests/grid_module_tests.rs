@@ -0,0 +1,106 @@
+extern crate htm;
+extern crate fnv;
+
+use htm::*;
+use fnv::FnvHashSet;
+use std::f32::consts::PI;
+
+fn feature(index: u32) -> FnvHashSet<Cell> {
+    (0..10).map(|i| Cell { column: index * 10 + i, cell: 0 }).collect()
+}
+
+#[test]
+fn path_integration_wraps_around() {
+    let mut module = GridModule::new(10, 10.0, 0.0);
+    module.phases.push((0.05, 0.05));
+    assert_eq!(vec![0], module.active_cells());
+
+    module.move_by(1.0, 0.0);
+    assert_eq!(vec![10], module.active_cells());
+    module.move_by(-2.0, 0.0);
+    assert_eq!(vec![90], module.active_cells());
+    // a full period comes back to the same cell
+    module.move_by(0.0, 10.0);
+    assert_eq!(vec![90], module.active_cells());
+
+    let mut rotated = GridModule::new(10, 10.0, PI / 2.0);
+    rotated.phases.push((0.05, 0.05));
+    rotated.move_by(1.0, 0.0);
+    assert_eq!(vec![1], rotated.active_cells());
+}
+
+#[test]
+fn rejects_modules_without_cells_or_scale() {
+    assert_eq!(Some(GridError::NoCells), GridModule::try_new(0, 10.0, 0.0).err());
+    assert_eq!(Some(GridError::InvalidScale(0.0)), GridModule::try_new(10, 0.0, 0.0).err());
+    assert_eq!(Some(GridError::NoCells), GridLocationLayer::try_with_modules(3, 0, 5.0, 1.4, 0).err());
+    assert_eq!(5, GridLocationLayer::try_with_modules(3, 8, 5.0, 1.4, 5).unwrap().column_offset);
+}
+
+#[test]
+fn sensory_input_anchors_location() {
+    let mut layer = GridLocationLayer::with_modules(4, 8, 5.0, 1.4, 0);
+    assert_eq!(256, layer.num_cells());
+
+    // nothing is learned without a location
+    assert!(!layer.sensory_compute(&feature(0), true));
+
+    layer.activate_random_location();
+    let mut learned = Vec::new();
+    for f in 0..3 {
+        for _ in 0..3 {
+            assert!(layer.sensory_compute(&feature(f), true));
+        }
+        learned.push(layer.sdr());
+        layer.move_by(1.0, 0.5);
+    }
+    assert_eq!(4, learned[0].len());
+
+    layer.reset();
+    assert!(layer.sdr().is_empty());
+    assert!(!layer.sensory_compute(&feature(7), false));
+
+    assert!(layer.sensory_compute(&feature(1), false));
+    assert_eq!(learned[1], layer.sdr());
+    // anchoring snaps to cell centers, path integration stays within one cell of the learned location
+    layer.move_by(1.0, 0.5);
+    for (&cell, &expected) in layer.sdr().iter().zip(learned[2].iter()) {
+        assert_eq!(expected / 64, cell / 64);
+        let axis_distance = |a: usize, b: usize| {
+            let d = if a > b { a - b } else { b - a };
+            d.min(8 - d)
+        };
+        assert!(axis_distance(cell % 64 / 8, expected % 64 / 8) <= 1);
+        assert!(axis_distance(cell % 8, expected % 8) <= 1);
+    }
+}
+
+#[test]
+fn grid_cells_as_temporal_memory_context() {
+    let mut tm = TemporalMemory::new(32, 4);
+    tm.activation_threshold = 3;
+    tm.min_threshold = 2;
+    tm.max_new_synapse_count = 16;
+
+    let mut layer = GridLocationLayer::with_modules(4, 8, 5.0, 1.4, 32);
+    layer.activate_random_location();
+    let location = layer.active_cells();
+    assert!(location.iter().all(|cell| cell.column >= 32));
+
+    let mut inside = FnvHashSet::default();
+    inside.insert(Cell { column: 3, cell: 0 });
+    assert_eq!(Err(TMError::ExternalColumnInRange { column: 3, columns: 32 }), tm.try_compute_external(&[0], &inside, true));
+
+    for _ in 0..5 {
+        tm.compute_external(&[0, 1, 2, 3], &location, true);
+        assert_eq!(location, tm.state.external_cells);
+        tm.compute(&[4, 5, 6, 7], true);
+        assert!(tm.state.external_cells.is_empty());
+        tm.reset();
+    }
+
+    // the location alone predicts what was sensed there
+    tm.compute_external(&[10, 11], &location, false);
+    let predicted: Vec<usize> = tm.predicted_columns(0.0).iter().map(|p| p.column).collect();
+    assert!([4, 5, 6, 7].iter().all(|c| predicted.contains(c)));
+}
@@ -0,0 +1,96 @@
+extern crate htm;
+
+use htm::*;
+
+#[test]
+fn distances() {
+    let a = [1, 2, 3, 4];
+    let b = [3, 4, 5, 6, 7, 8, 9, 10, 11];
+    assert_eq!(2.0, KNNDistance::Overlap.distance(&a, &b));
+    assert_eq!(9.0, KNNDistance::Hamming.distance(&a, &b));
+    assert!((KNNDistance::Cosine.distance(&a, &b) - (1.0 - 2.0 / 6.0)).abs() < 1e-6);
+    assert_eq!(0.0, KNNDistance::Cosine.distance(&a, &a));
+    assert_eq!(1.0, KNNDistance::Cosine.distance(&[], &a));
+}
+
+#[test]
+fn nearest_neighbours_vote() {
+    let mut knn = KNNClassifier::new(3, KNNDistance::Hamming);
+    assert_eq!(None, knn.infer(&[1, 2, 3]));
+
+    knn.learn(&[1, 2, 3, 4], 'a');
+    knn.learn(&[1, 2, 3, 5], 'a');
+    knn.learn(&[10, 11, 12, 13], 'b');
+    knn.learn(&[10, 11, 12, 14], 'b');
+
+    // the input order and duplicates do not matter
+    assert_eq!(Some('a'), knn.infer(&[3, 2, 1, 1]));
+    assert_eq!(Some('b'), knn.infer(&[12, 10, 11]));
+    assert_eq!(vec![(0, 1.0), (1, 1.0)], knn.neighbours(&[1, 2, 3])[..2].to_vec());
+
+    let votes = knn.votes(&[1, 2, 3, 4]);
+    assert_eq!('a', votes[0].0);
+    assert!((votes[0].1 - 2.0 / 3.0).abs() < 1e-6);
+
+    // a close `b` outweighs two distant `a` with distance weighting
+    let mut knn = KNNClassifier::new(3, KNNDistance::Hamming);
+    knn.learn(&[1, 2, 3, 4], 'b');
+    knn.learn(&[1, 20, 30, 40], 'a');
+    knn.learn(&[1, 21, 31, 41], 'a');
+    assert_eq!(Some('a'), knn.infer(&[1, 2, 3, 4]));
+    knn.distance_weighting = true;
+    assert_eq!(Some('b'), knn.infer(&[1, 2, 3, 4]));
+}
+
+#[test]
+fn prototype_limit_replaces_the_oldest() {
+    let mut knn = KNNClassifier::new(1, KNNDistance::Overlap);
+    knn.max_prototypes = Some(2);
+    knn.learn(&[1, 2], 0);
+    knn.learn(&[3, 4], 1);
+    knn.learn(&[5, 6], 2);
+    assert_eq!(2, knn.len());
+    assert_eq!(vec![1, 2], knn.prototypes().iter().map(|prototype| prototype.label).collect::<Vec<_>>());
+    assert_eq!(Some(1), knn.infer(&[1, 3, 4]));
+}
+
+#[test]
+fn removes_partitions() {
+    let mut knn = KNNClassifier::new(1, KNNDistance::Cosine);
+    knn.learn_partition(&[1, 2, 3], "first", Some(0));
+    knn.learn_partition(&[4, 5, 6], "second", Some(1));
+    knn.learn_partition(&[1, 2, 4], "third", Some(1));
+    knn.learn(&[7, 8, 9], "fourth");
+
+    assert_eq!(Some("third"), knn.infer(&[1, 2, 4]));
+    assert_eq!(2, knn.remove_partition(1));
+    assert_eq!(0, knn.remove_partition(1));
+    assert_eq!(2, knn.len());
+    assert_eq!(Some("first"), knn.infer(&[1, 2, 4]));
+}
+
+#[test]
+fn few_shot_on_spatial_pooler_columns() {
+    let mut sp = SpatialPooler::builder(vec![100], vec![256])
+        .full_potential_radius()
+        .global_inhibition(true)
+        .num_active_columns_per_inh_area(10.0)
+        .build()
+        .unwrap();
+    let mut knn = KNNClassifier::new(1, KNNDistance::Overlap);
+
+    // a class is a block of 20 bits, a sample misses one of them
+    let sample = |class: usize, missing: usize| -> Vec<bool> {
+        (0..100).map(|bit| bit / 20 == class && bit != class * 20 + missing).collect()
+    };
+    for class in 0..5 {
+        for missing in 0..3 {
+            sp.compute(&sample(class, missing), false);
+            knn.learn(&sp.winner_columns, class);
+        }
+    }
+    for class in 0..5 {
+        sp.compute(&sample(class, 10), false);
+        assert_eq!(Some(class), knn.infer(&sp.winner_columns));
+    }
+}
@@ -0,0 +1,102 @@
+extern crate htm;
+
+use htm::*;
+
+fn close(expected: f64, actual: Option<f64>) -> bool {
+    actual.map_or(false, |actual| (expected - actual).abs() < 1e-9)
+}
+
+#[test]
+fn confusion_matrix_precision_and_recall() {
+    let mut metrics = ClassificationMetrics::new();
+    assert_eq!(None, metrics.accuracy());
+    for &(actual, predicted) in &[(0, Some(0)), (0, Some(1)), (1, Some(1)), (1, Some(1)), (2, Some(1)), (2, None)] {
+        metrics.record(actual, predicted);
+    }
+
+    assert_eq!(vec![vec![1, 1, 0], vec![0, 2, 0], vec![0, 1, 0]], metrics.confusion);
+    assert_eq!(3, metrics.num_classes());
+    assert!(close(0.5, metrics.accuracy()));
+    assert!(close(1.0, metrics.precision(0)));
+    assert!(close(0.5, metrics.precision(1)));
+    assert_eq!(None, metrics.precision(2));
+    assert!(close(0.5, metrics.recall(0)));
+    assert!(close(1.0, metrics.recall(1)));
+    assert!(close(0.0, metrics.recall(2)));
+    assert_eq!(None, metrics.recall(3));
+}
+
+#[test]
+fn numeric_errors_over_a_window() {
+    let mut metrics = ErrorMetrics::new(4);
+    assert_eq!(None, metrics.rmse());
+    metrics.record(1000.0, 0.0);
+    for &(actual, predicted) in &[(10.0, 11.0), (20.0, 18.0), (0.0, 1.0), (10.0, 10.0)] {
+        metrics.record(actual, predicted);
+    }
+
+    // the first record left the window
+    assert!(close((10.0 + 10.0) / 3.0, metrics.mape()));
+    assert!(close(4.0 / 40.0 * 100.0, metrics.alt_mape()));
+    assert!(close((6.0f64 / 4.0).sqrt(), metrics.rmse()));
+    assert!(close((6.0f64 / 4.0).sqrt() / 50.0f64.sqrt(), metrics.nrmse()));
+
+    metrics.record_likelihood(1.0);
+    metrics.record_likelihood(0.5);
+    assert!(close(0.5f64.ln().abs() / 2.0, metrics.nll()));
+    metrics.record_likelihood(0.0);
+    assert!(metrics.nll().unwrap().is_finite());
+
+    let mut constant = ErrorMetrics::new(10);
+    constant.record(5.0, 4.0);
+    constant.record(5.0, 6.0);
+    assert_eq!(None, constant.nrmse());
+    assert!(close(1.0, constant.rmse()));
+}
+
+#[test]
+fn predictions_are_compared_after_their_step() {
+    let mut classifier = SDRClassifier::new(vec![0, 2], 0.1, 0.3, 30);
+    let mut metrics = PredictionMetrics::new(vec![0, 2], 100);
+
+    // buckets cycle through 0, 1, 2, pattern `i` encodes bucket `i`
+    for record in 0..300 {
+        let bucket = record as usize % 3;
+        let pattern: Vec<usize> = (bucket * 5..bucket * 5 + 5).collect();
+        let value = bucket as f64 * 10.0;
+        let result = classifier.compute(record, bucket, value, &pattern, true, true);
+        metrics.record(bucket, &value, &result);
+    }
+
+    let zero = metrics.classification_metrics(0).unwrap();
+    let two = metrics.classification_metrics(2).unwrap();
+    assert_eq!(300, zero.total);
+    // the first two records have no two step prediction to compare with
+    assert_eq!(298, two.total);
+    assert!(zero.accuracy().unwrap() > 0.95);
+    assert!(two.accuracy().unwrap() > 0.95);
+
+    let errors = metrics.error_metrics(2).unwrap();
+    assert!(close(0.0, errors.rmse()));
+    assert!(close(0.0, errors.alt_mape()));
+    assert!(errors.nll().unwrap() < 0.1);
+    assert!(metrics.error_metrics(1).is_none());
+}
+
+#[test]
+fn categories_only_get_classification_metrics() {
+    let mut classifier: SDRClassifier<String> = SDRClassifier::new(vec![1], 0.1, 0.3, 30);
+    let mut metrics = PredictionMetrics::new(vec![1], 10);
+    for record in 0..60 {
+        let bucket = record as usize % 2;
+        let label = if bucket == 0 { "even" } else { "odd" }.to_string();
+        let pattern: Vec<usize> = (bucket * 5..bucket * 5 + 5).collect();
+        let result = classifier.compute(record, bucket, label.clone(), &pattern, true, true);
+        metrics.record(bucket, &label, &result);
+    }
+
+    assert_eq!(59, metrics.classification[0].total);
+    assert!(metrics.classification[0].accuracy().unwrap() > 0.9);
+    assert_eq!(None, metrics.errors[0].rmse());
+    assert!(metrics.errors[0].nll().is_some());
+}
@@ -0,0 +1,69 @@
+extern crate htm;
+
+use htm::*;
+
+fn create_model() -> HtmPredictionModel {
+    let encoder = ScalarEncoder::new(21, 0.0, 10.0, 120, false);
+    let mut model = HtmPredictionModel::new_with_size(vec![Box::new(encoder)], 0, vec![0, 1], 1024, 4);
+    model.init().unwrap();
+    model
+}
+
+#[test]
+fn learns_to_predict_a_repeating_sequence() {
+    let mut model = create_model();
+    let sequence = [1.0, 3.0, 5.0, 7.0, 9.0];
+
+    let first = model.run(&[sequence[0]]);
+    assert_eq!(0, first.record_num);
+    assert_eq!(1.0, first.anomaly_score);
+    assert!(first.bucket_index.is_some());
+
+    for _ in 0..30 {
+        model.reset();
+        for &value in &sequence {
+            model.run(&[value]);
+        }
+    }
+
+    model.reset();
+    for (index, &value) in sequence.iter().enumerate().take(sequence.len() - 1) {
+        let result = model.run(&[value]);
+        assert_eq!(value, result.actual_value);
+        if index > 0 {
+            assert_eq!(0.0, result.anomaly_score);
+        }
+
+        let next = sequence[index + 1];
+        assert_eq!(Some(next), result.best_prediction(1));
+        assert_eq!(Some(value), result.best_prediction(0));
+
+        let (step, ref predictions) = result.multi_step_predictions[1];
+        assert_eq!(1, step);
+        assert_eq!(next, predictions[0].0);
+        assert!(predictions.windows(2).all(|w| w[0].1 >= w[1].1));
+        assert_eq!(Some(value), result.bucket_values[result.bucket_index.unwrap()]);
+    }
+}
+
+#[test]
+fn unexpected_values_are_anomalous() {
+    let mut model = create_model();
+    for _ in 0..30 {
+        model.reset();
+        for &value in &[2.0, 4.0, 6.0] {
+            model.run(&[value]);
+        }
+    }
+    model.reset();
+    model.run(&[2.0]);
+    assert_eq!(0.0, model.run(&[4.0]).anomaly_score);
+    assert!(model.run(&[9.0]).anomaly_score > 0.9);
+}
+
+#[test]
+fn raw_anomaly() {
+    assert_eq!(0.0, htm::raw_anomaly_score(&[], &[1, 2]));
+    assert_eq!(0.5, htm::raw_anomaly_score(&[1, 3], &[1, 2]));
+    assert_eq!(1.0, htm::raw_anomaly_score(&[4], &[]));
+}
@@ -0,0 +1,142 @@
+extern crate htm;
+
+use htm::*;
+use std::rc::Rc;
+use std::cell::RefCell;
+
+/** Outputs bit `step % 4` and records what it receives on `input`. */
+struct Probe {
+    step: usize,
+    received: Rc<RefCell<Vec<Vec<usize>>>>,
+}
+
+impl Probe {
+    fn new() -> (Probe, Rc<RefCell<Vec<Vec<usize>>>>) {
+        let received = Rc::new(RefCell::new(Vec::new()));
+        (Probe { step: 0, received: received.clone() }, received)
+    }
+}
+
+impl Region for Probe {
+    fn inputs(&self) -> Vec<Port> {
+        vec![Port::new("input", DataType::Sdr)]
+    }
+
+    fn outputs(&self) -> Vec<Port> {
+        vec![Port::new("output", DataType::Sdr)]
+    }
+
+    fn compute(&mut self, inputs: &RegionInputs, _learn: bool) -> Result<bool, HtmError> {
+        self.step += 1;
+        let received = inputs.sdr("input").map(|(active, _)| active.to_vec()).unwrap_or_default();
+        self.received.borrow_mut().push(received);
+        Ok(true)
+    }
+
+    fn output(&self, name: &str) -> Option<RegionData> {
+        match name {
+            "output" => Some(RegionData::Sdr { width: 4, active: vec![self.step % 4] }),
+            _ => None,
+        }
+    }
+}
+
+fn scalar_sensor(values: Vec<f64>) -> SensorRegion {
+    SensorRegion::new(ScalarEncoder::new(3, 0.0, 10.0, 20, false), values.into_iter())
+}
+
+#[test]
+fn links_are_validated() {
+    let mut net = Network::new();
+    net.add_region("sensor", scalar_sensor(vec![1.0])).unwrap();
+    net.add_region("probe", Probe::new().0).unwrap();
+
+    assert_eq!(Err(NetworkError::DuplicateRegion("probe".to_string())), net.add_region("probe", Probe::new().0));
+    assert_eq!(Err(NetworkError::UnknownRegion("tm".to_string())), net.link("sensor", "encoded", "tm", "bottom_up"));
+    assert_eq!(Err(NetworkError::UnknownOutput { region: "sensor".to_string(), output: "bits".to_string() }),
+               net.link("sensor", "bits", "probe", "input"));
+    assert_eq!(Err(NetworkError::TypeMismatch { output: DataType::Scalar, input: DataType::Sdr }),
+               net.link("sensor", "value", "probe", "input"));
+    assert_eq!(Err(NetworkError::OutOfOrder { source: "probe".to_string(), destination: "probe".to_string() }),
+               net.link("probe", "output", "probe", "input"));
+    assert!(net.link_feedback("probe", "output", "probe", "input").is_ok());
+    assert!(net.link("sensor", "encoded", "probe", "input").is_ok());
+    assert_eq!(2, net.links().len());
+}
+
+#[test]
+fn phases_order_regions_and_feedback_is_delayed() {
+    let mut net = Network::new();
+    let (upper, upper_received) = Probe::new();
+    let (lower, lower_received) = Probe::new();
+    net.add_region_with_phase("upper", 1, upper).unwrap();
+    net.add_region_with_phase("lower", 0, lower).unwrap();
+    assert_eq!(vec!["lower", "upper"], net.region_names());
+
+    net.link("lower", "output", "upper", "input").unwrap();
+    net.link_feedback("upper", "output", "lower", "input").unwrap();
+
+    assert_eq!(3, net.run(3).unwrap());
+    assert_eq!(3, net.iteration);
+    // forward links see the output of the same step
+    assert_eq!(vec![vec![1], vec![2], vec![3]], *upper_received.borrow());
+    // feedback links see the output of the previous step
+    assert_eq!(vec![vec![0], vec![1], vec![2]], *lower_received.borrow());
+}
+
+#[test]
+fn sdr_links_into_one_input_are_concatenated() {
+    let mut net = Network::new();
+    let (sink, received) = Probe::new();
+    net.add_region("a", Probe::new().0).unwrap();
+    net.add_region("b", Probe::new().0).unwrap();
+    net.add_region("sink", sink).unwrap();
+    net.link("a", "output", "sink", "input").unwrap();
+    net.link("b", "output", "sink", "input").unwrap();
+
+    net.run(1).unwrap();
+    assert_eq!(vec![vec![1, 5]], *received.borrow());
+}
+
+#[test]
+fn sensor_to_classifier_pipeline() {
+    let values: Vec<f64> = (0..50).map(|i| (i % 5) as f64 * 2.0).collect();
+
+    let mut sp = SpatialPooler::new(vec![20], vec![64]);
+    sp.potential_radius = 20;
+    sp.global_inhibition = true;
+    sp.num_active_columns_per_inh_area = 4.0;
+    sp.init().unwrap();
+
+    let mut net = Network::new();
+    net.add_region("sensor", scalar_sensor(values)).unwrap();
+    net.add_region("sp", SPRegion::new(sp)).unwrap();
+    net.add_region("tm", TMRegion::new(TemporalMemory::new(64, 4))).unwrap();
+    net.add_region("classifier", ClassifierRegion::new(SDRClassifier::new(vec![0, 1], 0.1, 0.3, 64 * 4))).unwrap();
+    net.link("sensor", "encoded", "sp", "bottom_up").unwrap();
+    net.link("sp", "active_columns", "tm", "bottom_up").unwrap();
+    net.link("tm", "active_cells", "classifier", "pattern").unwrap();
+    net.link("sensor", "value", "classifier", "value").unwrap();
+
+    assert_eq!(50, net.run(100).unwrap());
+    assert_eq!(50, net.iteration);
+
+    match net.output("sp", "active_columns") {
+        Some(RegionData::Sdr { width, active }) => {
+            assert_eq!(64, width);
+            assert_eq!(4, active.len());
+        }
+        other => panic!("unexpected output {:?}", other),
+    }
+    match net.output("tm", "active_cells") {
+        Some(RegionData::Sdr { width, active }) => {
+            assert_eq!(256, width);
+            assert!(!active.is_empty());
+        }
+        other => panic!("unexpected output {:?}", other),
+    }
+    match net.output("classifier", "probabilities") {
+        Some(RegionData::Probabilities(dists)) => assert_eq!(2, dists.len()),
+        other => panic!("unexpected output {:?}", other),
+    }
+}
@@ -0,0 +1,72 @@
+extern crate htm;
+
+use htm::*;
+use std::sync::{Arc, Mutex};
+
+fn assert_send<T: Send>() {}
+
+#[test]
+fn algorithms_with_observers_are_send() {
+    assert_send::<SpatialPooler>();
+    assert_send::<TemporalMemory>();
+}
+
+#[derive(Default)]
+struct SPEvents {
+    boosts: usize,
+    bumps: usize,
+    connected: usize,
+    disconnected: usize,
+}
+
+struct SPCounter(Arc<Mutex<SPEvents>>);
+
+impl LearningObserver for SPCounter {
+    fn proximal_connection_changed(&mut self, _column: usize, _input: usize, connected: bool) {
+        let mut events = self.0.lock().unwrap();
+        if connected {
+            events.connected += 1;
+        } else {
+            events.disconnected += 1;
+        }
+    }
+
+    fn boost_changed(&mut self, _column: usize, old: f32, new: f32) {
+        assert!(old != new);
+        self.0.lock().unwrap().boosts += 1;
+    }
+
+    fn column_bumped(&mut self, _column: usize) {
+        self.0.lock().unwrap().bumps += 1;
+    }
+}
+
+#[test]
+fn spatial_pooler_reports_learning_events() {
+    let events = Arc::new(Mutex::new(SPEvents::default()));
+    let mut sp = SpatialPooler::builder(vec![100], vec![64])
+        .full_potential_radius()
+        .global_inhibition(true)
+        .num_active_columns_per_inh_area(4.0)
+        .potential_pct(0.2)
+        .stimulus_threshold(3.0)
+        .min_pct_overlap_duty_cycles(0.5)
+        .max_boost(5.0)
+        .update_period(5)
+        .observer(Box::new(SPCounter(events.clone())))
+        .build()
+        .unwrap();
+
+    // moving blocks of 10 bits
+    for record in 0..50 {
+        let start = (record * 7) % 90;
+        let input: Vec<bool> = (0..100).map(|bit| bit >= start && bit < start + 10).collect();
+        sp.compute(&input, true);
+    }
+
+    let events = events.lock().unwrap();
+    assert!(events.connected > 0);
+    assert!(events.disconnected > 0);
+    assert!(events.boosts > 0);
+    assert!(events.bumps > 0);
+}
@@ -0,0 +1,167 @@
+extern crate htm;
+
+use htm::*;
+use std::env;
+
+#[test]
+fn defaults_match_the_constructors() {
+    let mut sp = SpatialPooler::new(vec![32, 32], vec![64, 64]);
+    sp.global_inhibition = true;
+    sp.num_active_columns_per_inh_area = 40.0;
+    assert_eq!(SpatialPoolerParams::default(), SpatialPoolerParams::from(&sp));
+
+    let tm = TemporalMemory::new(2048, 32);
+    assert_eq!(TemporalMemoryParams::default(), TemporalMemoryParams::from(&tm));
+
+    // missing keys take the defaults
+    assert_eq!(SpatialPoolerParams::default(), SpatialPoolerParams::from_json("{}").unwrap());
+    // and build a pooler
+    let pooler = SpatialPoolerParams::default().build().unwrap();
+    assert_eq!(40.0, pooler.num_active_columns_per_inh_area);
+    assert!(SpatialPoolerParams::from_json(r#"{ "inputDimensions": [100], "columnDimensions": [256] }"#).unwrap().build().is_ok());
+    assert_eq!(TemporalMemoryParams::default(), TemporalMemoryParams::from_toml("").unwrap());
+}
+
+#[test]
+fn accepts_nupic_names() {
+    let sp = SpatialPoolerParams::from_json(r#"{
+        "inputDimensions": [100],
+        "columnDimensions": [256],
+        "potentialPct": 0.8,
+        "globalInhibition": true,
+        "numActiveColumnsPerInhArea": 5,
+        "synPermConnected": 0.2,
+        "maxBoost": 3.0
+    }"#).unwrap();
+    assert_eq!(vec![100], sp.input_dimensions);
+    assert_eq!(0.8, sp.potential_pct);
+    assert!(sp.global_inhibition);
+    assert_eq!(0.2, sp.syn_perm_connected);
+    assert_eq!(3.0, sp.max_boost);
+
+    let pooler = sp.build().unwrap();
+    assert_eq!(100, pooler.num_inputs);
+    assert_eq!(256, pooler.num_columns);
+    assert_eq!(5.0, pooler.num_active_columns_per_inh_area);
+
+    let tm = TemporalMemoryParams::from_toml(r#"
+        columnCount = 256
+        cellsPerColumn = 8
+        initialPerm = 0.3
+        permanenceInc = 0.05
+        newSynapseCount = 12
+        segment_eviction = "LowestPermanence"
+    "#).unwrap();
+    let memory = tm.build();
+    assert_eq!(256, memory.columns);
+    assert_eq!(8, memory.cells);
+    assert_eq!(0.3, memory.initial_permanence);
+    assert_eq!(0.05, memory.permanence_increment);
+    assert_eq!(12, memory.max_new_synapse_count);
+    assert_eq!(EvictionPolicy::LowestPermanence, memory.segment_eviction);
+}
+
+#[test]
+fn accepts_opf_widths() {
+    let sp = SpatialPoolerParams::from_json(r#"{ "inputWidth": 400, "columnCount": 2048 }"#).unwrap();
+    assert_eq!(vec![400], sp.input_dimensions);
+    assert_eq!(vec![2048], sp.column_dimensions);
+    let sp = SpatialPoolerParams::from_toml("columnCount = [32, 64]").unwrap();
+    assert_eq!(vec![32, 64], sp.column_dimensions);
+}
+
+#[test]
+fn rejects_boost_strength() {
+    // NuPIC's exponential boost factor has no equivalent here
+    match SpatialPoolerParams::from_json(r#"{ "boostStrength": 3.0 }"#) {
+        Err(ParamsError::Json(err)) => assert!(err.contains("boostStrength")),
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn parameters_of_a_used_pooler_rebuild_it() {
+    let mut params = SpatialPoolerParams::default();
+    params.input_dimensions = vec![64];
+    params.column_dimensions = vec![128];
+    // the whole input, saved as it was configured
+    params.potential_radius = -1;
+    params.global_inhibition = true;
+    params.num_active_columns_per_inh_area = 5.0;
+    params.seed = 7;
+
+    let mut sp = params.build().unwrap();
+    let input: Vec<bool> = (0..64).map(|bit| bit % 3 == 0).collect();
+    sp.compute(&input, true);
+    let saved = SpatialPoolerParams::from(&sp);
+    assert_eq!(params, saved);
+    let mut rebuilt = SpatialPoolerParams::from_json(&saved.to_json().unwrap()).unwrap().build().unwrap();
+    let mut fresh = params.build().unwrap();
+    rebuilt.compute(&input, false);
+    fresh.compute(&input, false);
+    assert_eq!(fresh.winner_columns, rebuilt.winner_columns);
+
+    let mut tm_params = TemporalMemoryParams::default();
+    tm_params.columns = 64;
+    tm_params.seed = 9;
+    let mut tm = tm_params.build();
+    tm.compute(&[1, 2, 3], true);
+    tm.compute(&[4, 5, 6], true);
+    assert_eq!(tm_params, TemporalMemoryParams::from(&tm));
+}
+
+#[test]
+fn rejects_unknown_keys() {
+    match SpatialPoolerParams::from_json(r#"{ "potentialPtc": 0.8 }"#) {
+        Err(ParamsError::Json(err)) => assert!(err.contains("potentialPtc")),
+        other => panic!("unexpected {:?}", other),
+    }
+    match TemporalMemoryParams::from_toml("cells_per_colum = 4") {
+        Err(ParamsError::Toml(err)) => assert!(err.contains("cells_per_colum")),
+        other => panic!("unexpected {:?}", other),
+    }
+    assert!(EncoderParams::from_json(r#"{ "type": "ScalarEncoder", "w": 21, "minval": 0, "maxval": 1, "n": 100, "clip": true }"#).is_err());
+}
+
+#[test]
+fn round_trips_through_files() {
+    let mut sp = SpatialPoolerParams::default();
+    sp.input_dimensions = vec![28, 28];
+    sp.potential_radius = 5;
+    let mut tm = TemporalMemoryParams::default();
+    tm.cells_per_column = 4;
+    tm.segment_eviction = EvictionPolicy::FewestConnected;
+
+    let dir = env::temp_dir();
+    for extension in &["json", "toml"] {
+        let sp_path = dir.join(format!("htm_params_sp_{}.{}", std::process::id(), extension));
+        let tm_path = dir.join(format!("htm_params_tm_{}.{}", std::process::id(), extension));
+        sp.save(&sp_path).unwrap();
+        tm.save(&tm_path).unwrap();
+        assert_eq!(sp, SpatialPoolerParams::load(&sp_path).unwrap());
+        assert_eq!(tm, TemporalMemoryParams::load(&tm_path).unwrap());
+        std::fs::remove_file(sp_path).unwrap();
+        std::fs::remove_file(tm_path).unwrap();
+    }
+
+    assert_eq!(Err(ParamsError::UnknownFormat("params.yaml".to_string())), sp.save("params.yaml"));
+}
+
+#[test]
+fn builds_encoders() {
+    let scalar = EncoderParams::from_json(r#"{ "type": "ScalarEncoder", "w": 21, "minval": 0, "maxval": 10, "n": 120 }"#).unwrap();
+    assert_eq!(120, scalar.build().unwrap().size());
+    assert_eq!(scalar, EncoderParams::from_toml(&scalar.to_toml().unwrap()).unwrap());
+
+    let delta = EncoderParams::from_toml("type = \"DeltaEncoder\"\nwidth = 21\nsize = 100\n").unwrap();
+    match delta {
+        EncoderParams::DeltaEncoder(ref params) => assert_eq!(300, params.window_size),
+        ref other => panic!("unexpected {:?}", other),
+    }
+    assert_eq!(100, delta.build().unwrap().size());
+
+    let ambiguous = ScalarEncoderParams { width: 21, min: 0.0, max: 1.0, size: Some(100), resolution: Some(0.1), radius: None, wrap: false };
+    assert!(ambiguous.build().is_err());
+    let even = ScalarEncoderParams { width: 20, min: 0.0, max: 1.0, size: Some(100), resolution: None, radius: None, wrap: false };
+    assert!(even.build().is_err());
+}
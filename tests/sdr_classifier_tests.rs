@@ -0,0 +1,282 @@
+extern crate htm;
+
+use htm::*;
+
+/** Six disjoint patterns of 5 bits, repeated in order, pattern `i` encodes bucket `i`. */
+fn pattern(index: usize) -> Vec<usize> {
+    (index * 5..index * 5 + 5).collect()
+}
+
+fn most_probable(dist: &[f32]) -> usize {
+    dist.iter()
+        .enumerate()
+        .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
+        .unwrap()
+        .0
+}
+
+fn train(classifier: &mut SDRClassifier<f64>, records: u32) {
+    for record in 0..records {
+        let index = record as usize % 6;
+        classifier.compute(record, index, index as f64, &pattern(index), true, false);
+    }
+}
+
+#[test]
+fn predicts_non_contiguous_steps() {
+    let mut classifier = SDRClassifier::new(vec![1, 5, 24], 0.1, 0.3, 30);
+    train(&mut classifier, 600);
+
+    let result = classifier.compute(600, 0, 0.0, &pattern(0), false, true);
+    assert_eq!(vec![1, 5, 24], result.steps());
+    for &(step, ref dist) in &result.distributions {
+        assert_eq!(6, dist.len());
+        assert_eq!(step % 6, most_probable(dist), "step {}", step);
+    }
+}
+
+#[test]
+fn predicts_steps_beyond_u8() {
+    let mut classifier = SDRClassifier::new(vec![1, 300], 0.1, 0.3, 30);
+    train(&mut classifier, 1200);
+
+    let result = classifier.compute(1200, 2, 2.0, &pattern(2), false, true);
+    assert_eq!(300, result.distributions[1].0);
+    assert_eq!(3, most_probable(&result.distributions[0].1));
+    assert_eq!(2, most_probable(&result.distributions[1].1));
+}
+
+#[test]
+fn single_step_without_step_zero() {
+    let mut classifier = SDRClassifier::new(vec![1], 0.1, 0.3, 30);
+    train(&mut classifier, 120);
+
+    let result = classifier.compute(120, 0, 0.0, &pattern(4), false, true);
+    assert_eq!(1, result.distributions.len());
+    assert_eq!(5, most_probable(&result.distributions[0].1));
+}
+
+#[test]
+fn numeric_bucket_values_are_running_averages() {
+    let mut classifier = SDRClassifier::new(vec![0], 0.1, 0.5, 10);
+    classifier.compute(0, 0, 10.0, &[1, 2], true, true);
+    assert_eq!(Some(10.0), classifier.get_value(0));
+    classifier.compute(1, 0, 20.0, &[1, 2], true, true);
+    assert_eq!(Some(15.0), classifier.get_value(0));
+    classifier.compute(2, 0, 15.0, &[1, 2], true, true);
+    assert_eq!(Some(15.0), classifier.get_value(0));
+
+    let mut integers: SDRClassifier<u8> = SDRClassifier::new(vec![0], 0.1, 0.25, 10);
+    integers.compute(0, 0, 10, &[1], true, true);
+    integers.compute(1, 0, 18, &[1], true, true);
+    assert_eq!(Some(12), integers.get_value(0));
+}
+
+#[test]
+fn integer_buckets_move_by_small_updates() {
+    // 10 + 0.3 * (11 - 10) rounds back to 10, the average must be kept unrounded
+    let mut classifier: SDRClassifier<u32> = SDRClassifier::new(vec![0], 0.1, 0.3, 10);
+    classifier.compute(0, 0, 10, &[1], true, true);
+    classifier.compute(1, 0, 11, &[1], true, true);
+    assert_eq!(Some(10), classifier.get_value(0));
+    classifier.compute(2, 0, 11, &[1], true, true);
+    assert_eq!(Some(11), classifier.get_value(0));
+    for record in 3..20 {
+        classifier.compute(record, 0, 11, &[1], true, true);
+    }
+    assert_eq!(Some(11), classifier.get_value(0));
+
+    let mut negative: SDRClassifier<i64> = SDRClassifier::new(vec![0], 0.1, 0.1, 10);
+    negative.compute(0, 0, 0, &[1], true, true);
+    for record in 1..30 {
+        negative.compute(record, 0, -3, &[1], true, true);
+    }
+    assert_eq!(Some(-3), negative.get_value(0));
+}
+
+#[test]
+fn categories_keep_their_value() {
+    let mut classifier: SDRClassifier<String> = SDRClassifier::new(vec![0], 0.1, 0.3, 10);
+    classifier.compute(0, 1, "cat".to_string(), &[1, 2], true, true);
+    classifier.compute(1, 1, "dog".to_string(), &[1, 2], true, true);
+    assert_eq!(Some("dog".to_string()), classifier.get_value(1));
+    assert_eq!(None, classifier.weighted_value(&[0.5, 0.5]));
+}
+
+#[test]
+fn weighted_value_uses_bucket_probabilities() {
+    let mut classifier = SDRClassifier::new(vec![0], 0.1, 0.3, 10);
+    classifier.compute(0, 0, 10.0, &[1], true, false);
+    classifier.compute(1, 2, 30.0, &[2], true, false);
+
+    assert_eq!(Some(15.0), classifier.weighted_value(&[0.75, 0.0, 0.25]));
+    // bucket 1 has no value, its probability is ignored
+    assert_eq!(Some(20.0), classifier.weighted_value(&[0.25, 0.5, 0.25]));
+    assert_eq!(None, classifier.weighted_value(&[0.0, 0.0, 0.0]));
+}
+
+#[test]
+fn classification_accessors() {
+    let mut classifier = SDRClassifier::new(vec![0, 1], 0.1, 0.3, 30);
+    train(&mut classifier, 120);
+
+    let result = classifier.compute(120, 3, 3.0, &pattern(3), false, true);
+    assert_eq!(vec![0, 1], result.steps());
+    assert_eq!(Some(3), result.most_probable_bucket(0));
+    assert_eq!(Some(4), result.most_probable_bucket(1));
+    assert_eq!(Some(4.0), result.most_probable_value(1));
+    assert_eq!(Some(&5.0), result.actual_value(5));
+    assert_eq!(6, result.actual_values.len());
+
+    let dist = result.stats(1).unwrap();
+    assert_eq!(6, dist.len());
+    assert_eq!(Some(dist[4]), result.stat(1, 4));
+    assert_eq!(None, result.stat(1, 6));
+    assert_eq!(None, result.stats(2));
+    assert_eq!(None, result.most_probable_bucket(2));
+
+    let top = result.top_k(1, 6);
+    assert_eq!((4, dist[4]), top[0]);
+    assert!(top.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+    assert_eq!(dist.iter().filter(|&&probability| probability > 0.0).count(), top.len());
+    assert_eq!(vec![top[0]], result.top_k(1, 1));
+    assert!(result.weighted_value(1).is_some());
+
+    let without_inference = classifier.compute(121, 4, 4.0, &pattern(4), false, false);
+    assert!(without_inference.distributions.is_empty());
+    assert_eq!(None, without_inference.most_probable_value(0));
+    assert_eq!(Some(&4.0), without_inference.actual_value(4));
+}
+
+#[test]
+fn softmax_predicts_and_sums_to_one() {
+    let mut classifier = SDRClassifier::new_with_mode(vec![1, 5], 0.1, 0.3, 30, ClassifierMode::Softmax);
+    assert_eq!(ClassifierMode::Softmax, classifier.mode());
+    train(&mut classifier, 600);
+
+    let result = classifier.compute(600, 0, 0.0, &pattern(0), false, true);
+    for &(step, ref dist) in &result.distributions {
+        assert!((dist.iter().sum::<f32>() - 1.0).abs() < 1e-5);
+        assert!(dist.iter().all(|&probability| probability > 0.0));
+        assert_eq!(Some(step % 6), result.most_probable_bucket(step));
+    }
+}
+
+#[test]
+fn softmax_probabilities_are_calibrated() {
+    // pattern 0 is followed by bucket 1 three times out of four, by bucket 2 otherwise
+    let mut classifier = SDRClassifier::new_with_mode(vec![1], 0.05, 0.3, 30, ClassifierMode::Softmax);
+    let mut record = 0;
+    for round in 0..2000 {
+        let next = if round % 4 == 3 { 2 } else { 1 };
+        classifier.compute(record, 0, 0.0, &pattern(0), true, false);
+        classifier.compute(record + 1, next, next as f64, &pattern(next), true, false);
+        record += 2;
+    }
+
+    let result = classifier.compute(record, 0, 0.0, &pattern(0), false, true);
+    assert!((result.stat(1, 1).unwrap() - 0.75).abs() < 0.1, "{:?}", result.stats(1));
+    assert!((result.stat(1, 2).unwrap() - 0.25).abs() < 0.1, "{:?}", result.stats(1));
+}
+
+#[test]
+fn softmax_is_numerically_stable() {
+    let mut classifier = SDRClassifier::new_with_mode(vec![0], 1000.0, 0.3, 30, ClassifierMode::Softmax);
+    train(&mut classifier, 300);
+
+    let result = classifier.compute(300, 0, 0.0, &pattern(0), false, true);
+    let dist = result.stats(0).unwrap();
+    assert!(dist.iter().all(|probability| probability.is_finite()));
+    assert_eq!(Some(0), result.most_probable_bucket(0));
+
+    // nothing active, every bucket is equally likely
+    let empty = classifier.compute(301, 0, 0.0, &[], false, true);
+    assert!(empty.stats(0).unwrap().iter().all(|&probability| (probability - 1.0 / 6.0).abs() < 1e-6));
+}
+
+#[test]
+fn multi_label_records_share_the_probability() {
+    let mut classifier: SDRClassifier<String> = SDRClassifier::new_with_mode(vec![0], 0.1, 0.3, 30, ClassifierMode::Softmax);
+    let label = |bucket: usize| (bucket, format!("event {}", bucket));
+    for record in 0..600 {
+        let labels = match record % 3 {
+            0 => vec![label(0), label(2)],
+            1 => vec![label(1)],
+            _ => vec![label(1), label(2), label(3)],
+        };
+        let index = record as usize % 3;
+        classifier.compute_multi(record, &labels, &pattern(index), true, false);
+    }
+
+    let first = classifier.compute_multi(600, &[], &pattern(0), false, true);
+    let dist = first.stats(0).unwrap();
+    assert!((dist[0] - 0.5).abs() < 0.05 && (dist[2] - 0.5).abs() < 0.05, "{:?}", dist);
+    assert_eq!(vec![0, 2], first.labels(0));
+    assert_eq!(vec!["event 0".to_string(), "event 2".to_string()], first.label_values(0));
+
+    let second = classifier.compute_multi(601, &[], &pattern(1), false, true);
+    assert_eq!(vec![1], second.labels(0));
+    let third = classifier.compute_multi(602, &[], &pattern(2), false, true);
+    assert_eq!(vec![1, 2, 3], third.labels(0));
+
+    // a rare label needs a lower threshold than the others
+    classifier.set_label_threshold(0.4).unwrap();
+    classifier.set_bucket_threshold(3, 0.2).unwrap();
+    assert_eq!(0.4, classifier.label_threshold(0));
+    assert_eq!(0.2, classifier.label_threshold(3));
+    let third = classifier.compute_multi(603, &[], &pattern(2), false, true);
+    assert_eq!(vec![3], third.labels(0));
+}
+
+#[test]
+fn multi_label_input_is_validated() {
+    let mut classifier = SDRClassifier::new(vec![0], 0.1, 0.3, 30);
+    assert_eq!(Some(ClassifierError::DuplicateBucket(2)),
+               classifier.try_compute_multi(0, &[(2, 2.0), (1, 1.0), (2, 2.0)], &[1], true, true).err());
+    assert_eq!(Err(ClassifierError::ThresholdOutOfRange(1.5)), classifier.set_bucket_threshold(1, 1.5));
+    assert_eq!(1.0, classifier.label_threshold(0));
+
+    // a single label is the same as `compute`
+    let result = classifier.compute_multi(0, &[(1, 1.0)], &[1], true, true);
+    assert_eq!(vec![0.5, 0.5], result.thresholds);
+    assert_eq!(Some(1.0), classifier.get_value(1));
+}
+
+#[test]
+fn input_width_grows_with_the_patterns() {
+    let mut classifier = SDRClassifier::new(vec![1], 0.1, 0.3, 10);
+    assert_eq!(10, classifier.input_width());
+
+    // the patterns live far beyond the initial width
+    for record in 0..120 {
+        let index = record as usize % 6;
+        let far: Vec<usize> = pattern(index).iter().map(|bit| bit + 100_000).collect();
+        classifier.compute(record, index, index as f64, &far, true, false);
+    }
+    assert_eq!(100_030, classifier.input_width());
+
+    let far: Vec<usize> = pattern(2).iter().map(|bit| bit + 100_000).collect();
+    let result = classifier.compute(120, 2, 2.0, &far, false, true);
+    assert_eq!(Some(3), result.most_probable_bucket(1));
+
+    // bits that never learned have no weights
+    let unknown = classifier.compute(121, 0, 0.0, &[5, 200_000], false, true);
+    assert_eq!(Some(&[0.0; 6][..]), unknown.stats(1));
+    assert_eq!(200_001, classifier.input_width());
+}
+
+#[test]
+fn distributions_follow_the_bucket_count() {
+    // the inference buffers are reused across computes and must be resized to the buckets seen so far
+    let mut classifier = SDRClassifier::new(vec![0, 1], 0.1, 0.3, 30);
+    let mut max_bucket = 0;
+    for (record, &bucket) in [0usize, 7, 3, 12, 1, 12, 30].iter().enumerate() {
+        max_bucket = max_bucket.max(bucket);
+        classifier.compute(2 * record as u32, bucket, bucket as f64, &pattern(bucket % 6), true, false);
+        let result = classifier.compute(2 * record as u32 + 1, 0, 0.0, &pattern(bucket % 6), false, true);
+        for &(step, ref dist) in &result.distributions {
+            assert_eq!(max_bucket + 1, dist.len(), "record {} step {}", record, step);
+        }
+        assert_eq!(max_bucket + 1, result.actual_values.len());
+    }
+}
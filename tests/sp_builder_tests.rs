@@ -0,0 +1,69 @@
+extern crate htm;
+
+use htm::*;
+
+fn builder() -> SpatialPoolerBuilder {
+    SpatialPooler::builder(vec![10, 10], vec![16, 16])
+        .global_inhibition(true)
+        .num_active_columns_per_inh_area(10.0)
+}
+
+#[test]
+fn builds_an_initialized_pooler() {
+    let mut sp = builder()
+        .full_potential_radius()
+        .potential_pct(0.8)
+        .syn_perm_connected(0.2)
+        .seed(7)
+        .build()
+        .unwrap();
+    assert_eq!(-1, sp.potential_radius);
+    assert_eq!(0.2, sp.syn_perm_options.connected);
+    assert_eq!(0.02, sp.syn_perm_options.below_stimulus_inc);
+
+    let input: Vec<bool> = (0..100).map(|i| i % 3 == 0).collect();
+    sp.compute(&input, true);
+    assert_eq!(10, sp.winner_columns.len());
+}
+
+#[test]
+fn rejects_invalid_parameters() {
+    let cases = vec![
+        (SpatialPooler::builder(vec![], vec![16]).num_active_columns_per_inh_area(1.0).build(),
+         SPError::EmptyDimensions { input_dimensions: vec![], column_dimensions: vec![16] }),
+        (SpatialPooler::builder(vec![100], vec![16, 16]).num_active_columns_per_inh_area(1.0).build(),
+         SPError::DimensionMismatch { input_dimensions: 1, column_dimensions: 2 }),
+        (builder().potential_pct(1.5).build(), SPError::PotentialPctOutOfRange(1.5)),
+        (builder().potential_pct(0.0).build(), SPError::PotentialPctOutOfRange(0.0)),
+        (builder().init_connected_pct(-0.1).build(), SPError::InitConnectedPctOutOfRange(-0.1)),
+        (builder().syn_perm_bounds(0.5, 0.5).build(), SPError::InvalidPermanenceBounds { min: 0.5, max: 0.5 }),
+        (builder().syn_perm_bounds(0.0, 0.3).syn_perm_connected(0.4).build(), SPError::ConnectedOutOfBounds { connected: 0.4, min: 0.0, max: 0.3 }),
+        (builder().syn_perm_active_inc(-0.1).build(), SPError::NegativePermanenceChange { active_inc: -0.1, inactive_dec: 0.008 }),
+        (builder().update_period(0).build(), SPError::ZeroPeriod { duty_cycle_period: 1000, update_period: 0 }),
+        (builder().max_boost(0.5).build(), SPError::MaxBoostBelowOne(0.5)),
+        (builder().num_active_columns_per_inh_area(0.0).local_area_density(0.8).build(),
+         SPError::InvalidInhibitionParameters { num_active_columns_per_inh_area: 0.0, local_area_density: 0.8 }),
+    ];
+    for (result, expected) in cases {
+        match result {
+            Err(err) => assert_eq!(expected, err),
+            Ok(_) => panic!("expected {}", expected),
+        }
+    }
+}
+
+#[test]
+fn init_validates_public_fields() {
+    let mut sp = SpatialPooler::new(vec![100], vec![64]);
+    sp.global_inhibition = true;
+    sp.num_active_columns_per_inh_area = 4.0;
+    sp.potential_radius = -2;
+    assert_eq!(Err(SPError::NegativePotentialRadius(-2)), sp.init());
+    assert_eq!(Err(SPError::NotInitialized), sp.try_compute(&vec![false; 100], false));
+
+    // -1 covers the whole input
+    sp.potential_radius = -1;
+    assert_eq!(Ok(()), sp.init());
+    assert_eq!(-1, sp.potential_radius);
+    assert_eq!(Ok(()), sp.try_compute(&vec![false; 100], false));
+}
@@ -38,7 +38,7 @@ pub fn create_sp() -> SpatialPooler {
     sp.duty_cycle_period = 30;
     sp.max_boost = 10.0;
 
-    sp.init();
+    sp.init().unwrap();
 
     sp
 }
@@ -1,5 +1,6 @@
 extern crate htm;
 extern crate rand;
+extern crate serde_json;
 
 use htm::*;
 use rand::Rng;
@@ -85,9 +86,9 @@ pub fn test_activate_correctly_predictive_cells() {
     }
 
     tm.compute(&active_columns, true);
-    assert_eq!(expected_active_cells.len(), tm.active_cells.len());
+    assert_eq!(expected_active_cells.len(), tm.state.active_cells.len());
     for cell in &expected_active_cells {
-        assert_eq!(true, tm.active_cells.contains(&cell));
+        assert_eq!(true, tm.state.active_cells.contains(&cell));
     }
 }
 
@@ -99,9 +100,9 @@ pub fn test_burst_unpredicted_columns() {
     let bursting_cells = [ tm.get_cell(0), tm.get_cell(1), tm.get_cell(2), tm.get_cell(3) ];
     
     tm.compute(&active_columns, true);
-    assert_eq!(bursting_cells.len(), tm.active_cells.len());
+    assert_eq!(bursting_cells.len(), tm.state.active_cells.len());
     for cell in &bursting_cells {
-        assert_eq!(true, tm.active_cells.contains(&cell));
+        assert_eq!(true, tm.state.active_cells.contains(&cell));
     }
 }
 
@@ -122,13 +123,13 @@ pub fn test_zero_active_columns() {
 
     tm.compute(&previous_active_columns, true);
 
-    assert_eq!(false, tm.active_cells.len() == 0);
-    assert_eq!(false, tm.winner_cells.len() == 0);
+    assert_eq!(false, tm.state.active_cells.len() == 0);
+    assert_eq!(false, tm.state.winner_cells.len() == 0);
     assert_eq!(false, tm.get_predictive_cells().len() == 0);
     
     tm.compute(&[], true);
-    assert_eq!(true, tm.active_cells.len() == 0);
-    assert_eq!(true, tm.winner_cells.len() == 0);
+    assert_eq!(true, tm.state.active_cells.len() == 0);
+    assert_eq!(true, tm.state.winner_cells.len() == 0);
     assert_eq!(true, tm.get_predictive_cells().len() == 0);
 }
 
@@ -160,9 +161,9 @@ pub fn test_predicted_active_cells_are_always_winners() {
    tm.compute(&previous_active_columns, false); // learn=false
    tm.compute(&active_columns, false); // learn=false
     
-   assert_eq!(expected_winner_cells.len(), tm.winner_cells.len());
+   assert_eq!(expected_winner_cells.len(), tm.state.winner_cells.len());
    for cell in &expected_winner_cells {
-       assert_eq!(true, tm.winner_cells.contains(&cell));
+       assert_eq!(true, tm.state.winner_cells.contains(&cell));
    }
     
 }
@@ -353,20 +354,20 @@ pub fn test_new_segment_add_synapses_to_subset_of_winner_cells() {
     
     tm.compute(&previous_active_columns, true);
     
-    assert_eq!(3, tm.winner_cells.len());
+    assert_eq!(3, tm.state.winner_cells.len());
     
     tm.compute(&active_columns, true);
     
-    assert_eq!(1, tm.winner_cells.len());
+    assert_eq!(1, tm.state.winner_cells.len());
 
-    let winner_cell = tm.winner_cells.iter().next().unwrap();
+    let winner_cell = tm.state.winner_cells.iter().next().unwrap();
 
     assert_eq!(1, tm.get_segments(*winner_cell).len());
     assert_eq!(2, tm.get_segments(*winner_cell)[0].synapses.len());
     
     for synapse in &tm.get_segments(*winner_cell)[0].synapses {
         assert_approx_eq!(0.21, synapse.permanence, 0.01);
-        assert_eq!(true, tm.prev_winner_cells.contains(&synapse.cell));
+        assert_eq!(true, tm.state.prev_winner_cells.contains(&synapse.cell));
     }
 }
 
@@ -379,23 +380,23 @@ pub fn test_new_segment_add_synapses_to_all_winner_cells() {
     
     tm.compute(&previous_active_columns, true);
     
-    assert_eq!(3, tm.winner_cells.len());
+    assert_eq!(3, tm.state.winner_cells.len());
     
     tm.compute(&active_columns, true);
     
-    assert_eq!(1, tm.winner_cells.len());
+    assert_eq!(1, tm.state.winner_cells.len());
 
-    let winner_cell = tm.winner_cells.iter().next().unwrap();
+    let winner_cell = tm.state.winner_cells.iter().next().unwrap();
 
     assert_eq!(1, tm.get_segments(*winner_cell).len());
     
     let synps = &tm.get_segments(*winner_cell)[0].synapses;
     
-    assert_eq!(synps.len(), tm.prev_winner_cells.len());
+    assert_eq!(synps.len(), tm.state.prev_winner_cells.len());
 
     for synapse in synps {
         assert_approx_eq!(0.21, synapse.permanence, 0.01);
-        assert_eq!(true, tm.prev_winner_cells.contains(&synapse.cell));
+        assert_eq!(true, tm.state.prev_winner_cells.contains(&synapse.cell));
     }
 }
 
@@ -416,9 +417,9 @@ pub fn test_matching_segment_add_synapses_to_subset_of_winner_cells() {
 
     tm.compute(&previous_active_columns, true);
 
-    assert_eq!(previous_winner_cells.len(), tm.winner_cells.len());
+    assert_eq!(previous_winner_cells.len(), tm.state.winner_cells.len());
     for cell in &previous_winner_cells {
-        assert_eq!(true, tm.winner_cells.contains(&cell));
+        assert_eq!(true, tm.state.winner_cells.contains(&cell));
     }
 
     tm.compute(&active_columns, true);
@@ -454,9 +455,9 @@ pub fn test_matching_segment_add_synapses_to_all_winner_cells() {
 
     tm.compute(&previous_active_columns, true);
 
-    assert_eq!(previous_winner_cells.len(), tm.winner_cells.len());
+    assert_eq!(previous_winner_cells.len(), tm.state.winner_cells.len());
     for cell in &previous_winner_cells {
-        assert_eq!(true, tm.winner_cells.contains(&cell));
+        assert_eq!(true, tm.state.winner_cells.contains(&cell));
     }
 
     tm.compute(&active_columns, true);
@@ -502,9 +503,9 @@ pub fn test_active_segment_grow_synapses_according_to_potential_overlap() {
 
     tm.compute(&previous_active_columns, true);
 
-    assert_eq!(previous_winner_cells.len(), tm.winner_cells.len());
+    assert_eq!(previous_winner_cells.len(), tm.state.winner_cells.len());
     for cell in &previous_winner_cells {
-        assert_eq!(true, tm.winner_cells.contains(&cell));
+        assert_eq!(true, tm.state.winner_cells.contains(&cell));
     }
 
     tm.compute(&active_columns, true);
@@ -605,9 +606,9 @@ pub fn test_recycle_weakest_synapse_to_make_room_for_new_synapse() {
 
     tm.compute(&previous_active_columns, true);
 
-    assert_eq!(previous_winner_cells.len(), tm.winner_cells.len());
+    assert_eq!(previous_winner_cells.len(), tm.state.winner_cells.len());
     for cell in &previous_winner_cells {
-        assert_eq!(true, tm.winner_cells.contains(&cell));
+        assert_eq!(true, tm.state.winner_cells.contains(&cell));
     }
 
     tm.compute(&active_columns, true);
@@ -769,9 +770,9 @@ pub fn test_add_segment_to_cell_with_fewest_segments() {
         tm.compute(&previous_active_columns, true);
         tm.compute(&active_columns, true);
         
-        assert_eq!(active_cells.len(), tm.active_cells.len());
+        assert_eq!(active_cells.len(), tm.state.active_cells.len());
         for cell in &active_cells {
-           assert_eq!(true, tm.active_cells.contains(&cell));
+           assert_eq!(true, tm.state.active_cells.contains(&cell));
         }
         
         assert_eq!(3, tm.num_segments());
@@ -821,3 +822,458 @@ pub fn test_add_segment_to_cell_with_fewest_segments() {
 }
 
 
+
+#[test]
+pub fn test_rollout_follows_and_branches_predictions() {
+    let mut tm = create_tm();
+    tm.activation_threshold = 1;
+
+    let start_cells = [ tm.get_cell(0), tm.get_cell(1) ];
+    let links = [ (0, 4), (4, 8), (1, 13), (13, 17), (17, 22) ];
+    for &(from, to) in &links {
+        let mut seg = tm.create_segment(tm.get_cell(to));
+        seg.create_synapse(tm.get_cell(from), 0.5);
+        tm.add_segment(seg);
+    }
+
+    let iteration = tm.iteration;
+    let num_segments = tm.num_segments();
+    let start = start_cells.iter().cloned().collect();
+    let steps = tm.rollout(&start, 5, 4);
+
+    assert_eq!(3, steps.len());
+    assert_eq!(2, steps[0].len());
+    assert_eq!(vec![1], steps[0][0].columns);
+    assert_eq!(vec![3], steps[0][1].columns);
+    assert_eq!(None, steps[0][0].parent);
+
+    assert_eq!(2, steps[1].len());
+    assert_eq!(vec![2], steps[1][0].columns);
+    assert_eq!(Some(0), steps[1][0].parent);
+    assert_eq!(vec![4], steps[1][1].columns);
+    assert_eq!(Some(1), steps[1][1].parent);
+
+    assert_eq!(1, steps[2].len());
+    assert_eq!(vec![tm.get_cell(22)], steps[2][0].cells);
+    assert_eq!(Some(1), steps[2][0].parent);
+
+    let single = tm.rollout(&start, 5, 1);
+    assert_eq!(1, single[0].len());
+
+    assert_eq!(iteration, tm.iteration);
+    assert_eq!(num_segments, tm.num_segments());
+}
+
+#[test]
+pub fn test_connections_inspection() {
+    let mut tm = create_tm();
+
+    {
+    let mut seg = tm.create_segment(tm.get_cell(4));
+    seg.create_synapse(tm.get_cell(0), 0.6);
+    seg.create_synapse(tm.get_cell(1), 0.3);
+    tm.add_segment(seg);
+    }
+
+    {
+    let mut seg = tm.create_segment(tm.get_cell(5));
+    seg.create_synapse(tm.get_cell(0), 0.2);
+    tm.add_segment(seg);
+    }
+
+    let mut cells: Vec<Cell> = tm.cells_with_segments().collect();
+    cells.sort();
+    assert_eq!(vec![tm.get_cell(4), tm.get_cell(5)], cells);
+    assert_eq!(2, tm.segments().count());
+
+    let mut from_cell0: Vec<(Cell, f32)> = tm.synapses_for_presynaptic_cell(tm.get_cell(0))
+        .map(|(seg_ref, syn)| (seg_ref.cell, syn.permanence))
+        .collect();
+    from_cell0.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(2, from_cell0.len());
+    assert_approx_eq!(0.6, from_cell0[0].1);
+    assert_approx_eq!(0.2, from_cell0[1].1);
+    assert_eq!(1, tm.presynaptic_links(tm.get_cell(0)).filter(|link| link.is_connected()).count());
+    assert_eq!(0, tm.presynaptic_links(tm.get_cell(2)).count());
+
+    tm.compute(&[2], true);
+    tm.compute(&[3], true);
+    let seg = &tm.get_segments(tm.get_cell(4))[0];
+    assert_eq!(2, tm.segment_age(seg));
+    assert_eq!(1, seg.num_connected_synapses(tm.connected_permanence));
+    assert_eq!(2, seg.num_potential_synapses());
+
+    // column 3 burst and grew a segment onto the winner cell of column 2
+    let stats: Vec<ColumnStatistics> = tm.column_statistics().collect();
+    assert_eq!(2, stats.len());
+    assert_eq!(3, stats[1].column);
+    assert_eq!(1, stats[0].column);
+    assert_eq!(2, stats[0].cells_with_segments);
+    assert_eq!(2, stats[0].segments);
+    assert_eq!(3, stats[0].potential_synapses);
+    assert_eq!(1, stats[0].connected_synapses);
+}
+
+#[test]
+pub fn test_parallel_dendrites_match_serial() {
+    let mut serial = create_tm_custom(256, 4);
+    let mut parallel = create_tm_custom(256, 4);
+    parallel.parallel_dendrites = true;
+
+    let mut rnd = UniversalRng::from_seed([7,0,0,0]);
+    let sequence: Vec<Vec<usize>> = (0..8).map(|_| {
+        let mut columns: Vec<usize> = (0..40).map(|_| rnd.gen_range(0, 256)).collect();
+        columns.sort();
+        columns.dedup();
+        columns
+    }).collect();
+
+    for _ in 0..5 {
+        for columns in &sequence {
+            serial.compute(columns, true);
+            parallel.compute(columns, true);
+
+            assert_eq!(serial.state.active_cells, parallel.state.active_cells);
+            assert_eq!(serial.state.winner_cells, parallel.state.winner_cells);
+            assert_eq!(serial.get_predictive_cells().clone(), parallel.get_predictive_cells().clone());
+        }
+    }
+    assert_eq!(serial.num_segments(), parallel.num_segments());
+}
+
+#[test]
+pub fn test_invalid_active_columns_are_rejected() {
+    let mut tm = create_tm_custom(8, 4);
+    assert_eq!(8, tm.num_columns());
+    assert_eq!(32, tm.num_cells());
+
+    assert_eq!(Err(TMError::ColumnOutOfRange { column: 8, columns: 8 }), tm.try_compute(&[1, 8], true));
+    assert_eq!(Err(TMError::DuplicateColumn(2)), tm.try_compute(&[2, 2], true));
+    assert_eq!(Err(TMError::UnsortedColumns { previous: 3, column: 1 }), tm.try_compute(&[3, 1], true));
+    assert_eq!(0, tm.state.active_cells.len());
+    assert_eq!(1, tm.iteration);
+
+    assert_eq!(Ok(()), tm.try_compute(&[0, 7], true));
+    assert_eq!(8, tm.state.active_cells.len());
+}
+
+#[test]
+pub fn test_streams_keep_separate_activity() {
+    let mut tm = create_tm();
+
+    {
+    let mut seg = tm.create_segment(tm.get_cell(4));
+    seg.create_synapse(tm.get_cell(0), 0.5);
+    seg.create_synapse(tm.get_cell(1), 0.5);
+    seg.create_synapse(tm.get_cell(2), 0.5);
+    seg.create_synapse(tm.get_cell(3), 0.5);
+    tm.add_segment(seg);
+    }
+
+    let mut stream_a = TMState::new();
+    let mut stream_b = TMState::new();
+
+    tm.compute_with_state(&mut stream_a, &[0], false);
+    tm.compute_with_state(&mut stream_b, &[2], false);
+    tm.compute_with_state(&mut stream_a, &[1], false);
+    tm.compute_with_state(&mut stream_b, &[1], false);
+
+    // stream a predicted column 1, stream b did not and bursts
+    assert_eq!(1, stream_a.active_cells.len());
+    assert_eq!(true, stream_a.active_cells.contains(&tm.get_cell(4)));
+    assert_eq!(4, stream_b.active_cells.len());
+    assert_eq!(0, tm.state.active_cells.len());
+
+    let saved = stream_a.clone();
+    tm.swap_state(&mut stream_a);
+    assert_eq!(saved.active_cells, tm.state.active_cells);
+    assert_eq!(&saved.active_cells, tm.get_active_cells());
+    assert_eq!(&saved.winner_cells, tm.get_winner_cells());
+    assert_eq!(&saved.prev_active_cells, tm.get_prev_active_cells());
+}
+
+#[test]
+pub fn test_saved_state_survives_streams_that_do_not_learn() {
+    let mut tm = create_tm();
+
+    {
+    let mut seg = tm.create_segment(tm.get_cell(4));
+    seg.create_synapse(tm.get_cell(0), 0.5);
+    seg.create_synapse(tm.get_cell(1), 0.5);
+    seg.create_synapse(tm.get_cell(2), 0.5);
+    seg.create_synapse(tm.get_cell(3), 0.5);
+    tm.add_segment(seg);
+    }
+
+    let mut stream_a = TMState::new();
+    let mut stream_b = TMState::new();
+    tm.compute_with_state(&mut stream_a, &[0], false);
+    let saved = stream_a.segments_active.clone();
+    assert_eq!(1, saved.len());
+
+    // other streams may run in between as long as they do not learn
+    for &column in &[2, 1, 0, 3, 1] {
+        tm.compute_with_state(&mut stream_b, &[column], false);
+    }
+    assert_eq!(saved, stream_a.segments_active);
+    assert_eq!(tm.get_cell(4), saved[0].segment.cell);
+    assert_eq!(4, tm.get_segment(&saved[0].segment).synapses.len());
+
+    tm.compute_with_state(&mut stream_a, &[1], false);
+    assert_eq!(1, stream_a.active_cells.len());
+    assert_eq!(true, stream_a.active_cells.contains(&tm.get_cell(4)));
+}
+
+#[test]
+pub fn test_state_round_trips_through_serde() {
+    let mut tm = create_tm();
+
+    {
+    let mut seg = tm.create_segment(tm.get_cell(4));
+    seg.create_synapse(tm.get_cell(0), 0.5);
+    seg.create_synapse(tm.get_cell(1), 0.5);
+    seg.create_synapse(tm.get_cell(2), 0.5);
+    seg.create_synapse(tm.get_cell(3), 0.5);
+    tm.add_segment(seg);
+    }
+
+    let mut stream = TMState::new();
+    tm.compute_with_state(&mut stream, &[0], false);
+    assert_eq!(1, stream.segments_active.len());
+
+    let json = serde_json::to_string(&stream).unwrap();
+    let mut restored: TMState = serde_json::from_str(&json).unwrap();
+    assert_eq!(stream.active_cells, restored.active_cells);
+    assert_eq!(stream.winner_cells, restored.winner_cells);
+    assert_eq!(1, restored.segments_active.len());
+
+    // the restored stream continues where the saved one stopped
+    tm.compute_with_state(&mut stream, &[1], false);
+    tm.compute_with_state(&mut restored, &[1], false);
+    assert_eq!(stream.active_cells, restored.active_cells);
+    assert_eq!(true, restored.active_cells.contains(&tm.get_cell(4)));
+}
+
+#[test]
+pub fn test_forget_unused_segments() {
+    let mut tm = create_tm();
+    tm.min_threshold = 3;
+    tm.forgetting = Some(ForgettingOptions {
+        period: 1,
+        unused_iterations: 2,
+        permanence_decay: 0.1,
+    });
+
+    {
+    let mut seg = tm.create_segment(tm.get_cell(4));
+    seg.create_synapse(tm.get_cell(0), 0.5);
+    seg.create_synapse(tm.get_cell(1), 0.5);
+    seg.create_synapse(tm.get_cell(2), 0.05);
+    tm.add_segment(seg);
+    }
+
+    {
+    let mut seg = tm.create_segment(tm.get_cell(8));
+    seg.create_synapse(tm.get_cell(0), 0.5);
+    seg.create_synapse(tm.get_cell(1), 0.5);
+    seg.create_synapse(tm.get_cell(2), 0.5);
+    tm.add_segment(seg);
+    }
+
+    tm.compute(&[], true);
+    tm.compute(&[], true);
+    assert_eq!(3, tm.get_segments(tm.get_cell(4))[0].synapses.len());
+    assert_eq!(PruneReport::default(), tm.pruned);
+
+    tm.compute(&[], true);
+
+    for syn in &tm.get_segments(tm.get_cell(8))[0].synapses {
+        assert_approx_eq!(0.4, syn.permanence, 0.001);
+    }
+    assert_eq!(0, tm.get_segments(tm.get_cell(4))[0].synapses.len());
+    assert_eq!(1, tm.presynaptic_links(tm.get_cell(0)).count());
+    assert_eq!(PruneReport { segments_decayed: 2, synapses_removed: 3, segments_removed: 1 }, tm.pruned);
+
+    // the emptied slot of cell 4 is not reported as a segment
+    let stats: Vec<ColumnStatistics> = tm.column_statistics().collect();
+    assert_eq!(1, stats.len());
+    assert_eq!(tm.get_cell(8).column, stats[0].column);
+    assert_eq!(1, stats[0].cells_with_segments);
+    assert_eq!(1, stats[0].segments);
+    assert_eq!(3, stats[0].potential_synapses);
+}
+
+#[test]
+pub fn test_segment_eviction_policies() {
+    let policies = [ (EvictionPolicy::LeastRecentlyUsed, 0.2), (EvictionPolicy::FewestConnected, 0.6), (EvictionPolicy::LowestPermanence, 0.6) ];
+    for &(policy, kept) in &policies {
+        let mut tm = create_tm_cells(1);
+        tm.max_segments_per_cell = 2;
+        tm.segment_eviction = policy;
+        let cell = tm.get_cell(9);
+
+        // the weak segment is the most recently used one
+        tm.iteration = 5;
+        {
+        let mut seg = tm.create_segment(cell);
+        seg.create_synapse(tm.get_cell(0), 0.2);
+        seg.create_synapse(tm.get_cell(1), 0.2);
+        seg.create_synapse(tm.get_cell(2), 0.55);
+        tm.add_segment(seg);
+        }
+
+        tm.iteration = 1;
+        {
+        let mut seg = tm.create_segment(cell);
+        seg.create_synapse(tm.get_cell(3), 0.6);
+        seg.create_synapse(tm.get_cell(4), 0.6);
+        tm.add_segment(seg);
+        }
+
+        {
+        let mut seg = tm.create_segment(cell);
+        seg.create_synapse(tm.get_cell(5), 0.9);
+        tm.add_segment(seg);
+        }
+
+        let segments = tm.get_segments(cell);
+        assert_eq!(2, segments.len());
+        let survivor = segments.iter().find(|seg| seg.synapses[0].permanence < 0.8).unwrap();
+        assert_approx_eq!(kept, survivor.synapses[0].permanence, 0.001);
+        assert_eq!(1, tm.presynaptic_links(tm.get_cell(5)).count());
+    }
+}
+
+#[test]
+pub fn test_random_segment_eviction_is_seeded() {
+    let evict = |seed: u32| {
+        let mut tm = create_tm_cells(1);
+        tm.rand = UniversalRng::from_seed([seed, 0, 0, 0]);
+        tm.max_segments_per_cell = 2;
+        tm.segment_eviction = EvictionPolicy::Random;
+        let cell = tm.get_cell(9);
+
+        for i in 0..6 {
+            let mut seg = tm.create_segment(cell);
+            seg.create_synapse(tm.get_cell(i), 0.1 + 0.1 * i as f32);
+            tm.add_segment(seg);
+            assert_eq!(std::cmp::min(i as usize + 1, 2), tm.get_segments(cell).len());
+        }
+        // the segment added last always survives
+        assert_eq!(1, tm.presynaptic_links(tm.get_cell(5)).count());
+        let mut kept: Vec<u32> = tm.get_segments(cell).iter().map(|seg| seg.synapses[0].cell.column).collect();
+        kept.sort();
+        kept
+    };
+
+    assert_eq!(evict(42), evict(42));
+    assert_eq!(evict(7), evict(7));
+}
+
+#[derive(Default)]
+struct EventCounter {
+    created: usize,
+    grown: usize,
+    destroyed: usize,
+    connected: usize,
+}
+
+struct SharedCounter(std::sync::Arc<std::sync::Mutex<EventCounter>>);
+
+impl LearningObserver for SharedCounter {
+    fn segment_created(&mut self, _segment: &SegmentRef) {
+        self.0.lock().unwrap().created += 1;
+    }
+
+    fn synapse_grown(&mut self, _segment: &SegmentRef, _presynaptic: Cell, _permanence: f32) {
+        self.0.lock().unwrap().grown += 1;
+    }
+
+    fn synapse_destroyed(&mut self, _segment: &SegmentRef, _presynaptic: Cell) {
+        self.0.lock().unwrap().destroyed += 1;
+    }
+
+    fn synapse_connection_changed(&mut self, _segment: &SegmentRef, _presynaptic: Cell, connected: bool) {
+        if connected {
+            self.0.lock().unwrap().connected += 1;
+        }
+    }
+}
+
+#[test]
+pub fn test_observer_receives_learning_events() {
+    let mut tm = create_tm_cells(1);
+    tm.min_threshold = 1;
+    tm.max_new_synapse_count = 4;
+    tm.initial_permanence = 0.45;
+    let counter = std::sync::Arc::new(std::sync::Mutex::new(EventCounter::default()));
+    tm.observer = Some(Box::new(SharedCounter(counter.clone())));
+
+    tm.compute(&[0], true);
+    tm.compute(&[1], true);
+    {
+        let events = counter.lock().unwrap();
+        assert_eq!(1, events.created);
+        assert_eq!(1, events.grown);
+        assert_eq!(0, events.connected);
+    }
+
+    tm.reset();
+    tm.compute(&[0], true);
+    tm.compute(&[1], true);
+
+    let events = counter.lock().unwrap();
+    assert_eq!(1, events.created);
+    assert_eq!(1, events.connected);
+    assert_eq!(0, events.destroyed);
+}
+
+#[test]
+pub fn test_predicted_columns_confidence() {
+    let mut tm = create_tm();
+    let previous_active_cells = [ tm.get_cell(0), tm.get_cell(1), tm.get_cell(2), tm.get_cell(3) ];
+
+    // column 1: active segment with 4 active synapses
+    {
+    let mut seg = tm.create_segment(tm.get_cell(4));
+    for &cell in &previous_active_cells {
+        seg.create_synapse(cell, 0.5);
+    }
+    tm.add_segment(seg);
+    }
+
+    // column 2: active segment with 3 active synapses
+    {
+    let mut seg = tm.create_segment(tm.get_cell(8));
+    for &cell in &previous_active_cells[..3] {
+        seg.create_synapse(cell, 0.5);
+    }
+    tm.add_segment(seg);
+    }
+
+    // column 3: only matching with 2 active synapses
+    {
+    let mut seg = tm.create_segment(tm.get_cell(12));
+    for &cell in &previous_active_cells[..2] {
+        seg.create_synapse(cell, 0.3);
+    }
+    tm.add_segment(seg);
+    }
+
+    tm.compute(&[0], false);
+
+    let predictions = tm.predicted_columns(0.0);
+    assert_eq!(2, predictions.len());
+    assert_eq!(1, predictions[0].column);
+    assert_approx_eq!(4.0 / 7.0, predictions[0].confidence);
+    assert_eq!(2, predictions[1].column);
+
+    let predictions = tm.predicted_columns(0.5);
+    assert_eq!(3, predictions.len());
+    assert_eq!(3, predictions[2].column);
+    assert_approx_eq!(1.0 / 8.0, predictions[2].confidence);
+    let total: f32 = predictions.iter().map(|p| p.confidence).sum();
+    assert_approx_eq!(1.0, total);
+
+    assert_eq!(1, tm.top_predicted_columns(1, 0.5).len());
+}
@@ -0,0 +1,137 @@
+extern crate htm;
+
+use htm::*;
+
+fn create_tm() -> TemporalMemory {
+    let mut tm = TemporalMemory::new(64, 4);
+    tm.activation_threshold = 3;
+    tm.initial_permanence = 0.21;
+    tm.connected_permanence = 0.5;
+    tm.min_threshold = 2;
+    tm.max_new_synapse_count = 4;
+    tm.permanence_increment = 0.10;
+    tm.permanence_decrement = 0.10;
+    tm.rand = UniversalRng::from_seed([42,0,0,0]);
+    tm
+}
+
+fn sequence() -> Vec<Vec<usize>> {
+    (0..6).map(|i| (i * 8..i * 8 + 4).collect()).collect()
+}
+
+#[test]
+fn predicted_active_cells() {
+    let mut tm = create_tm();
+    let seq = sequence();
+    for _ in 0..10 {
+        for columns in &seq {
+            tm.compute(columns, true);
+        }
+        tm.reset();
+    }
+
+    tm.compute(&seq[0], false);
+    assert!(tm.state.predicted_active_cells.is_empty());
+    tm.compute(&seq[1], false);
+    assert_eq!(tm.state.predicted_active_cells.len(), 4);
+    assert!(tm.state.predicted_active_cells.iter().all(|cell| tm.state.active_cells.contains(cell)));
+}
+
+#[test]
+fn union_is_stable_over_learned_sequence() {
+    let mut tm = create_tm();
+    let seq = sequence();
+    for _ in 0..10 {
+        for columns in &seq {
+            tm.compute(columns, true);
+        }
+        tm.reset();
+    }
+
+    let mut up = UnionPooler::new(tm.num_cells(), 256);
+    up.num_active_columns = 8;
+    up.init();
+
+    let mut unions = Vec::new();
+    for columns in &seq {
+        tm.compute(columns, false);
+        up.compute_from_tm(&tm, true);
+        assert_eq!(up.active_columns.len(), 8);
+        assert!(up.union_sdr.len() <= 51);
+        unions.push(up.union_sdr.clone());
+    }
+
+    // every step adds to the union instead of replacing it
+    for pair in unions.windows(2) {
+        assert!(pair[0].iter().all(|c| pair[1].binary_search(c).is_ok()));
+    }
+    assert!(unions[5].len() > unions[0].len());
+
+    up.reset();
+    assert!(up.union_sdr.is_empty());
+    assert!(up.pooling_activation.iter().all(|&a| a == 0.0));
+}
+
+fn overlap(a: &[usize], b: &[usize]) -> usize {
+    a.iter().filter(|c| b.binary_search(c).is_ok()).count()
+}
+
+#[test]
+fn union_depends_on_learned_sequences() {
+    let mut tm = create_tm();
+    let seq = sequence();
+    for _ in 0..10 {
+        for columns in &seq {
+            tm.compute(columns, true);
+        }
+        tm.reset();
+    }
+
+    let mut up = UnionPooler::new(tm.num_cells(), 256);
+    up.num_active_columns = 8;
+    up.init();
+    let pool = |tm: &mut TemporalMemory, up: &mut UnionPooler, seq: &[Vec<usize>], learn: bool| -> Vec<Vec<usize>> {
+        tm.reset();
+        up.reset();
+        seq.iter().map(|columns| {
+            tm.compute(columns, false);
+            up.compute_from_tm(tm, learn);
+            up.union_sdr.clone()
+        }).collect()
+    };
+    for _ in 0..10 {
+        pool(&mut tm, &mut up, &seq, true);
+    }
+
+    let learned = pool(&mut tm, &mut up, &seq, false);
+    // the same sequence pools to the same union
+    assert_eq!(learned, pool(&mut tm, &mut up, &seq, false));
+    // once the sequence is predicted no column leaves the union
+    for pair in learned[1..].windows(2) {
+        assert_eq!(pair[0].len(), overlap(&pair[0], &pair[1]));
+    }
+
+    // a sequence of other columns bursts and pools to another union
+    let other: Vec<Vec<usize>> = (0..6).map(|i| (i * 8 + 4..i * 8 + 8).collect()).collect();
+    let unlearned = pool(&mut tm, &mut up, &other, false);
+    assert!(overlap(&learned[5], &unlearned[5]) * 4 < learned[5].len(), "{:?} {:?}", learned[5], unlearned[5]);
+}
+
+#[test]
+fn rejects_inputs_out_of_range() {
+    let mut up = UnionPooler::new(32, 64);
+    up.init();
+    assert_eq!(Err(UPError::InputOutOfRange { index: 32, size: 32 }), up.try_compute(&[1, 32], &[], true));
+    assert_eq!(Err(UPError::InputOutOfRange { index: 40, size: 32 }), up.try_compute(&[1], &[40], true));
+    assert_eq!(0, up.iteration_num);
+    assert_eq!(Ok(()), up.try_compute(&[1, 31], &[31], true));
+}
+
+#[test]
+fn rejects_compute_before_init() {
+    let mut up = UnionPooler::new(32, 64);
+    assert_eq!(Err(UPError::NotInitialized), up.try_compute(&[1, 31], &[31], true));
+    assert_eq!(0, up.iteration_num);
+    up.init();
+    assert_eq!(Ok(()), up.try_compute(&[1, 31], &[31], true));
+}